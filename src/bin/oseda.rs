@@ -3,11 +3,11 @@ use std::{error::Error, process};
 use clap::Parser;
 use oseda_cli::{
     cmd::{
-        check,
+        adopt, cache, check,
         deploy::{self},
         export::{self},
         fork::{self},
-        init, run,
+        hooks, init, plugin, run, status, template, theme, undeploy, upgrade,
     },
     Cli, Commands,
 };
@@ -21,6 +21,9 @@ fn main() {
         Commands::Init(options) => {
             init::init(options).map(|_| println!("Successfully initialized oseda project"))
         }
+        Commands::Adopt(options) => {
+            adopt::adopt(options).map(|_| println!("Successfully adopted oseda project"))
+        }
         Commands::Run => run::run()
             .map(|_| println!("Successfully ran oseda project"))
             .map_err(|e| e.into()),
@@ -31,7 +34,19 @@ fn main() {
             println!("Successfully deployed oseda project");
             println!("See deployment instructions...");
         }),
-        Commands::Fork => fork::fork(),
+        Commands::Status(options) => status::status(options),
+        Commands::Undeploy(options) => {
+            undeploy::undeploy(options).map(|_| println!("Successfully undeployed oseda project"))
+        }
+        Commands::Fork(options) => fork::fork(options),
+        Commands::Upgrade(options) => {
+            upgrade::upgrade(options).map(|_| println!("Successfully upgraded oseda project"))
+        }
+        Commands::Cache(options) => cache::cache(options),
+        Commands::Plugin(options) => plugin::plugin(options),
+        Commands::Theme(options) => theme::theme(options),
+        Commands::Hooks(options) => hooks::hooks(options),
+        Commands::Template(options) => template::template(options),
         Commands::Export(options) => export::export(options.clone())
             .map(|_| println!("Successfully export project to {0}", options.port)),
     };
@@ -0,0 +1,249 @@
+use std::{error::Error, fs, path::Path};
+
+use crate::{config::OsedaConfig, generated::write_if_changed};
+
+/// Where slide files live inside a project
+pub const SLIDES_DIR: &str = "slides";
+
+/// The generated module that imports every chapter, loaded by `src/main.js`
+pub const SLIDES_JS_PATH: &str = "src/slides.js";
+
+/// Slide files, in the order they are presented
+#[derive(Debug, Default, PartialEq)]
+pub struct ChapterPlan {
+    /// files to present, relative to slides/ -> e.g. `01-intro.md`
+    pub chapters: Vec<String>,
+    /// listed in `chapters` in the config, but not in slides/
+    pub missing: Vec<String>,
+    /// in slides/, but not listed in `chapters` in the config
+    pub orphaned: Vec<String>,
+}
+
+/// Lists the markdown and html files in slides/, sorted by name
+pub fn slide_files(project_dir: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let slides_dir = project_dir.join(SLIDES_DIR);
+    if !slides_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(slides_dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if format_of(&name).is_some() {
+            files.push(name);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Whether slides/ has markdown chapters, which need the markdown plugin
+pub fn has_markdown_slides(project_dir: &Path) -> bool {
+    slide_files(project_dir)
+        .unwrap_or_default()
+        .iter()
+        .any(|file| file.ends_with(".md"))
+}
+
+/// Works out the chapter order of a project
+///
+/// The `chapters` list in the config wins. Without one, files are ordered by
+/// their number prefix -> e.g. `2-borrowing.md` comes before `10-async.md`
+///
+/// # Arguments
+/// * `files` - the files in slides/, see `slide_files`
+/// * `listed` - the `chapters` from the config
+pub fn plan(files: &[String], listed: &[String]) -> ChapterPlan {
+    if listed.is_empty() {
+        let mut chapters = files.to_vec();
+        chapters.sort_by_key(|name| (number_prefix(name), name.clone()));
+
+        return ChapterPlan {
+            chapters,
+            ..Default::default()
+        };
+    }
+
+    let (chapters, missing) = listed
+        .iter()
+        .cloned()
+        .partition(|chapter| files.contains(chapter));
+
+    ChapterPlan {
+        chapters,
+        missing,
+        orphaned: files
+            .iter()
+            .filter(|file| !listed.contains(file))
+            .cloned()
+            .collect(),
+    }
+}
+
+/// The leading number of a file name, files without one go last
+fn number_prefix(name: &str) -> u64 {
+    let digits: String = name.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().unwrap_or(u64::MAX)
+}
+
+/// How `src/main.js` turns a file into slides
+fn format_of(name: &str) -> Option<&'static str> {
+    match Path::new(name).extension()?.to_str()? {
+        "md" => Some("markdown"),
+        "html" => Some("html"),
+        _ => None,
+    }
+}
+
+/// Builds the src/slides.js that imports the chapters in order
+pub fn slides_js(chapters: &[String]) -> String {
+    let mut js = String::from(
+        "// Generated by oseda from the files in slides/, do not edit.\n\
+         // Add chapters to slides/, or order them under \"chapters\" in oseda-config.json\n\n",
+    );
+
+    for (i, chapter) in chapters.iter().enumerate() {
+        // escaped like the file names below, slides/ can have quotes in file names
+        let specifier = format!("../{SLIDES_DIR}/{chapter}?raw");
+        js.push_str(&format!("import chapter{i} from {specifier:?};\n"));
+    }
+
+    js.push_str("\nexport default [\n");
+    for (i, chapter) in chapters.iter().enumerate() {
+        js.push_str(&format!(
+            "  {{ file: {:?}, format: {:?}, content: chapter{i} }},\n",
+            chapter,
+            format_of(chapter).unwrap_or("html")
+        ));
+    }
+    js.push_str("];\n");
+
+    js
+}
+
+/// Writes src/slides.js for the chapters in slides/, if they changed
+///
+/// # Arguments
+/// * `project_dir` - the project
+/// * `conf` - the project's config
+///
+/// # Returns
+/// * `Ok(true)` if src/slides.js was (re)written
+/// * `Ok(false)` if it was already up to date, the project has no slides/ or is an adopted deck
+/// * `Err` if chapters listed in the config are missing, or a file can't be read or written
+pub fn sync_slides(project_dir: &Path, conf: &OsedaConfig) -> Result<bool, Box<dyn Error>> {
+    if conf.adopted || (!project_dir.join(SLIDES_DIR).is_dir() && conf.chapters.is_empty()) {
+        return Ok(false);
+    }
+
+    let plan = plan(&slide_files(project_dir)?, &conf.chapters);
+    if !plan.missing.is_empty() {
+        return Err(format!(
+            "Chapters listed in oseda-config.json are missing from slides/: {}",
+            plan.missing.join(", ")
+        )
+        .into());
+    }
+
+    write_if_changed(
+        &project_dir.join(SLIDES_JS_PATH),
+        &slides_js(&plan.chapters),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_plan_chapter_order() {
+        let files = names(&[
+            "10-async.md",
+            "2-borrowing.md",
+            "1-intro.md",
+            "appendix.html",
+        ]);
+
+        let by_prefix = plan(&files, &[]);
+        assert_eq!(
+            by_prefix.chapters,
+            names(&[
+                "1-intro.md",
+                "2-borrowing.md",
+                "10-async.md",
+                "appendix.html"
+            ])
+        );
+
+        let listed = plan(
+            &files,
+            &names(&["1-intro.md", "10-async.md", "3-traits.md"]),
+        );
+        assert_eq!(listed.chapters, names(&["1-intro.md", "10-async.md"]));
+        assert_eq!(listed.missing, names(&["3-traits.md"]));
+        assert_eq!(listed.orphaned, names(&["2-borrowing.md", "appendix.html"]));
+    }
+
+    #[test]
+    fn test_slides_js_escapes_file_names() {
+        let js = slides_js(&names(&[r#"01-"quoted"\intro.md"#]));
+
+        assert!(js.contains(r#"import chapter0 from "../slides/01-\"quoted\"\\intro.md?raw";"#));
+        assert!(js.contains(r#"{ file: "01-\"quoted\"\\intro.md", format: "markdown""#));
+    }
+
+    #[test]
+    fn test_sync_slides() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(SLIDES_DIR)).unwrap();
+        fs::write(dir.path().join("slides/02-ownership.md"), "# Ownership").unwrap();
+        fs::write(dir.path().join("slides/01-intro.md"), "# Intro").unwrap();
+        fs::write(dir.path().join("slides/notes.txt"), "not a chapter").unwrap();
+
+        let mut conf: OsedaConfig = serde_json::from_str(
+            r##"{
+                "title": "IntroToRust",
+                "author": "JaneDoe",
+                "tags": ["ComputerScience"],
+                "last_updated": "2024-07-10T12:34:56Z",
+                "color": "#FF0000",
+                "description": "Learn rust"
+            }"##,
+        )
+        .unwrap();
+
+        assert!(sync_slides(dir.path(), &conf).unwrap());
+        assert!(!sync_slides(dir.path(), &conf).unwrap());
+
+        let js = fs::read_to_string(dir.path().join(SLIDES_JS_PATH)).unwrap();
+        let intro = js.find("../slides/01-intro.md?raw").unwrap();
+        let ownership = js.find("../slides/02-ownership.md?raw").unwrap();
+        assert!(intro < ownership);
+        assert!(js.contains(r#"{ file: "01-intro.md", format: "markdown", content: chapter0 }"#));
+        assert!(!js.contains("notes.txt"));
+
+        conf.chapters = names(&["02-ownership.md", "01-intro.md"]);
+        assert!(sync_slides(dir.path(), &conf).unwrap());
+        let js = fs::read_to_string(dir.path().join(SLIDES_JS_PATH)).unwrap();
+        assert!(js.find("02-ownership.md").unwrap() < js.find("01-intro.md").unwrap());
+
+        conf.chapters.push("03-traits.md".to_owned());
+        assert!(sync_slides(dir.path(), &conf).is_err());
+
+        // adopted decks load their slides themselves
+        conf.adopted = true;
+        fs::remove_file(dir.path().join(SLIDES_JS_PATH)).unwrap();
+        assert!(!sync_slides(dir.path(), &conf).unwrap());
+        assert!(!dir.path().join(SLIDES_JS_PATH).exists());
+    }
+}
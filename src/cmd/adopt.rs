@@ -0,0 +1,527 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use clap::Args;
+
+use crate::{
+    cmd::init::{self, InitOptions},
+    config,
+    package::PackageManifest,
+    pm::{self, PackageManager},
+    theme,
+};
+
+/// Options for the `oseda adopt` command
+#[derive(Args, Debug)]
+pub struct AdoptOptions {
+    /// The deck to adopt, defaults to the working directory
+    pub dir: Option<PathBuf>,
+
+    #[arg(long, num_args = 1.., value_delimiter=' ')]
+    pub tags: Option<Vec<String>>,
+
+    #[arg(long)]
+    pub color: Option<String>,
+
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// Package manager to use, detected from the deck's lockfile by default
+    #[arg(long)]
+    pub package_manager: Option<PackageManager>,
+
+    /// Only add the missing dependencies to package.json, without installing them
+    #[arg(long)]
+    pub no_install: bool,
+}
+
+/// What `oseda adopt` found in an existing deck
+#[derive(Debug, Default, PartialEq)]
+pub struct DeckInfo {
+    /// whether the deck has a package.json
+    pub has_package_json: bool,
+    /// reveal.js version from package.json -> e.g. `^4.6.0`
+    pub reveal_version: Option<String>,
+    /// bundlers from package.json -> e.g. `vite`, `webpack`
+    pub bundlers: Vec<String>,
+    /// reveal plugins the deck registers -> e.g. `RevealNotes`
+    pub plugins: Vec<String>,
+    /// markdown and html slide files, relative to the deck
+    pub slide_files: Vec<String>,
+    /// `<section>`s written straight into index.html
+    pub inline_sections: usize,
+    /// problems oseda can't fix on its own
+    pub incompatibilities: Vec<String>,
+}
+
+// directories that hold dependencies or build output, never slides
+const SKIPPED_DIRS: [&str; 4] = ["node_modules", "dist", ".git", ".oseda"];
+
+const BUNDLERS: [&str; 4] = ["vite", "webpack", "parcel", "rollup"];
+
+/// Brings an existing reveal.js deck under OSEDA
+///
+/// This will:
+/// - Inspect the deck's package.json, reveal plugins and slide files
+/// - Write an oseda-config.json, prompting for anything not passed as a flag
+/// - Add the packages `oseda run`, `check` and `deploy` need, and a .gitignore for them
+/// - Report what still needs fixing by hand
+///
+/// # Arguments
+/// * `opts` - options parsed from CLI flags
+///
+/// # Returns
+/// * `Ok(())` if the deck was adopted, even if there are incompatibilities left to fix
+/// * `Err` if the deck is already an Oseda project, or a file can't be read or written
+pub fn adopt(opts: AdoptOptions) -> Result<(), Box<dyn Error>> {
+    let dir = opts.dir.clone().unwrap_or_else(|| PathBuf::from("."));
+    let dir = dir.canonicalize()?;
+
+    if dir.join("oseda-config.json").exists() {
+        return Err(format!("{} is already an Oseda project", dir.display()).into());
+    }
+
+    let info = inspect(&dir)?;
+    print_findings(&info);
+
+    // check wants the title to match the directory name
+    let title = dir
+        .file_name()
+        .ok_or("Can't adopt the root directory")?
+        .to_string_lossy()
+        .into_owned();
+
+    let package_manager = opts
+        .package_manager
+        .unwrap_or_else(|| pm::detect(&dir, None));
+
+    let mut conf = config::create_conf(InitOptions {
+        title: Some(title.clone()),
+        tags: opts.tags,
+        color: opts.color,
+        ..Default::default()
+    })?;
+    conf.description = match opts.description {
+        Some(description) => description,
+        None => inquire::Text::new("Description: ").prompt()?,
+    };
+    conf.package_manager = Some(package_manager);
+    conf.adopted = true;
+
+    config::write_config(&dir, &conf)?;
+    println!("Wrote oseda-config.json");
+
+    let added = add_packages(&dir, &title)?;
+    if !added.is_empty() {
+        println!("Added to package.json: {}", added.join(", "));
+    }
+
+    if ensure_gitignore(&dir)? {
+        println!("Added node_modules and dist to .gitignore");
+    }
+
+    theme::sync_theme(&dir, &conf)?;
+    println!(
+        "Generated {} and {}, link the stylesheet from index.html or import the module to use the course color",
+        theme::THEME_CSS_PATH,
+        theme::THEME_JS_PATH
+    );
+
+    if !added.is_empty() && !opts.no_install {
+        init::install_dependencies(&dir, package_manager, false, &AtomicBool::new(false))?;
+    }
+
+    if !info.incompatibilities.is_empty() {
+        println!("\nOseda can't fix these, have a look before running `oseda check`:");
+        for problem in &info.incompatibilities {
+            println!("- {problem}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Looks through a deck for what oseda needs to know about it
+///
+/// # Arguments
+/// * `dir` - the deck's directory
+///
+/// # Returns
+/// * `Ok(DeckInfo)` with everything found, including problems oseda can't fix
+/// * `Err` if the directory or its package.json can't be read
+pub fn inspect(dir: &Path) -> Result<DeckInfo, Box<dyn Error>> {
+    let mut info = DeckInfo::default();
+
+    let package_json = dir.join("package.json");
+    if package_json.exists() {
+        let package_json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&package_json)?)?;
+        info.has_package_json = true;
+
+        let dependency = |name: &str| {
+            ["dependencies", "devDependencies"]
+                .iter()
+                .find_map(|section| package_json[section][name].as_str())
+                .map(str::to_owned)
+        };
+
+        info.reveal_version = dependency("reveal.js");
+        info.bundlers = BUNDLERS
+            .iter()
+            .filter(|bundler| dependency(bundler).is_some())
+            .map(|bundler| bundler.to_string())
+            .collect();
+    }
+
+    let index = fs::read_to_string(dir.join("index.html")).unwrap_or_default();
+    if index.is_empty() {
+        info.incompatibilities.push(
+            "There is no index.html at the top of the deck, vite builds the deck from it"
+                .to_owned(),
+        );
+    }
+    info.inline_sections = index.matches("<section").count();
+
+    let mut sources = vec![index.clone()];
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files)?;
+    for file in files {
+        let name = file.to_string_lossy().replace('\\', "/");
+        match file.extension().and_then(|ext| ext.to_str()) {
+            Some("md") if !is_readme(&file) => info.slide_files.push(name),
+            Some("html") if name != "index.html" => info.slide_files.push(name),
+            Some("js" | "mjs" | "ts") => sources.push(fs::read_to_string(dir.join(&file))?),
+            _ => {}
+        }
+    }
+    info.slide_files.sort();
+
+    for source in &sources {
+        for plugin in find_plugins(source) {
+            if !info.plugins.contains(&plugin) {
+                info.plugins.push(plugin);
+            }
+        }
+    }
+    info.plugins.sort();
+
+    if info.slide_files.is_empty() && info.inline_sections == 0 {
+        info.incompatibilities.push(
+            "No slides found, neither <section>s in index.html nor markdown or html slide files"
+                .to_owned(),
+        );
+    }
+
+    if let Some(major) = info
+        .reveal_version
+        .as_deref()
+        .and_then(|version| {
+            version
+                .trim_start_matches(['^', '~', '=', 'v'])
+                .split('.')
+                .next()
+        })
+        .and_then(|major| major.parse::<u32>().ok())
+    {
+        if major < 4 {
+            info.incompatibilities.push(format!(
+                "reveal.js {} predates the plugin API of reveal.js 4, upgrade it to 5.x",
+                info.reveal_version.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+
+    for bundler in info.bundlers.iter().filter(|b| *b != "vite") {
+        info.incompatibilities.push(format!(
+            "The deck is built with {bundler}, oseda builds with vite and ignores its config"
+        ));
+    }
+
+    if index.contains("\"dist/") || index.contains("'dist/") {
+        info.incompatibilities.push(
+            "index.html loads files from dist/, which vite build overwrites. Import reveal.js from node_modules instead"
+                .to_owned(),
+        );
+    }
+
+    if has_local_classic_script(&index) {
+        info.incompatibilities.push(
+            "index.html has classic <script src> tags, vite only bundles <script type=\"module\">"
+                .to_owned(),
+        );
+    }
+
+    if dir
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().contains(char::is_whitespace))
+    {
+        info.incompatibilities.push(
+            "The directory name has spaces, course titles can't. Rename the directory".to_owned(),
+        );
+    }
+
+    Ok(info)
+}
+
+/// Collects files below `dir`, relative to `root`, skipping dependencies and hidden directories
+fn walk(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if entry.file_type()?.is_dir() {
+            if !SKIPPED_DIRS.contains(&name.as_str()) && !name.starts_with('.') {
+                walk(root, &path, files)?;
+            }
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_owned());
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether index.html loads a local script without `type="module"`, CDN scripts are fine
+fn has_local_classic_script(index: &str) -> bool {
+    index.match_indices("<script src=").any(|(start, tag)| {
+        let src = index[start + tag.len()..].trim_start_matches(['"', '\'']);
+        !["http://", "https://", "//"]
+            .iter()
+            .any(|remote| src.starts_with(remote))
+    })
+}
+
+fn is_readme(path: &Path) -> bool {
+    path.file_stem()
+        .is_some_and(|stem| stem.eq_ignore_ascii_case("readme"))
+}
+
+/// Finds reveal plugin names in a source file -> e.g. `RevealMarkdown`, `RevealNotes`
+fn find_plugins(source: &str) -> Vec<String> {
+    let mut plugins = Vec::new();
+
+    for (start, _) in source.match_indices("Reveal") {
+        let name: String = source[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+
+        // plugins are `Reveal` and a capitalized name, `Reveal` itself is the deck
+        let is_plugin = name.len() > "Reveal".len()
+            && name["Reveal".len()..].starts_with(|c: char| c.is_ascii_uppercase());
+        let word_start = source[..start]
+            .chars()
+            .last()
+            .is_none_or(|c| !c.is_ascii_alphanumeric());
+
+        if is_plugin && word_start && !plugins.contains(&name) {
+            plugins.push(name);
+        }
+    }
+
+    plugins
+}
+
+/// Adds the packages every Oseda project has to the deck's package.json
+///
+/// Packages the deck already has are left at their version
+///
+/// # Returns
+/// * `Ok(Vec<String>)` with the packages and scripts that were added
+/// * `Err` if package.json can't be read or written
+pub fn add_packages(dir: &Path, title: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let manifest = PackageManifest::base();
+    let path = dir.join("package.json");
+
+    if !path.exists() {
+        manifest.write_package_json(dir, title)?;
+        return Ok(manifest
+            .dependencies
+            .keys()
+            .chain(manifest.dev_dependencies.keys())
+            .cloned()
+            .collect());
+    }
+
+    let mut package_json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+    let has = |package_json: &serde_json::Value, name: &str| {
+        ["dependencies", "devDependencies"]
+            .iter()
+            .any(|section| package_json[section].get(name).is_some())
+    };
+
+    let mut added = Vec::new();
+    for (section, packages) in [
+        ("dependencies", &manifest.dependencies),
+        ("devDependencies", &manifest.dev_dependencies),
+        ("scripts", &manifest.scripts),
+    ] {
+        for (name, value) in packages {
+            let exists = match section {
+                "scripts" => package_json["scripts"].get(name).is_some(),
+                _ => has(&package_json, name),
+            };
+            if exists {
+                continue;
+            }
+
+            let listed = package_json
+                .as_object_mut()
+                .ok_or("package.json is not a JSON object")?
+                .entry(section)
+                .or_insert_with(|| serde_json::json!({}))
+                .as_object_mut()
+                .ok_or_else(|| format!("{section} in package.json is not a JSON object"))?;
+            listed.insert(name.clone(), value.clone().into());
+
+            added.push(match section {
+                "scripts" => format!("script {name}"),
+                _ => name.clone(),
+            });
+        }
+    }
+
+    if !added.is_empty() {
+        fs::write(path, serde_json::to_string_pretty(&package_json)? + "\n")?;
+    }
+
+    Ok(added)
+}
+
+/// Makes sure dependencies and build output are never deployed
+///
+/// # Returns
+/// * `Ok(true)` if .gitignore was changed
+fn ensure_gitignore(dir: &Path) -> Result<bool, Box<dyn Error>> {
+    let path = dir.join(".gitignore");
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+
+    let missing: Vec<&str> = ["node_modules/", "dist"]
+        .into_iter()
+        .filter(|entry| {
+            !existing
+                .lines()
+                .any(|line| line.trim().trim_end_matches('/') == entry.trim_end_matches('/'))
+        })
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(false);
+    }
+
+    let mut gitignore = existing;
+    if !gitignore.is_empty() && !gitignore.ends_with('\n') {
+        gitignore.push('\n');
+    }
+    gitignore.push_str("# added by oseda adopt\n");
+    for entry in missing {
+        gitignore.push_str(entry);
+        gitignore.push('\n');
+    }
+    fs::write(path, gitignore)?;
+
+    Ok(true)
+}
+
+fn print_findings(info: &DeckInfo) {
+    match &info.reveal_version {
+        Some(version) => println!("Found a reveal.js {version} deck"),
+        None if info.has_package_json => println!("Found a deck without reveal.js in package.json"),
+        None => println!("Found a deck without a package.json"),
+    }
+
+    if !info.bundlers.is_empty() {
+        println!("Built with {}", info.bundlers.join(", "));
+    }
+    if !info.plugins.is_empty() {
+        println!("Plugins: {}", info.plugins.join(", "));
+    }
+    if info.inline_sections > 0 {
+        println!("{} slide(s) written in index.html", info.inline_sections);
+    }
+    if !info.slide_files.is_empty() {
+        println!("Slide files: {}", info.slide_files.join(", "));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_inspect_deck() {
+        let dir = tempfile::tempdir().unwrap();
+        let deck = dir.path();
+        fs::create_dir_all(deck.join("slides")).unwrap();
+        fs::create_dir_all(deck.join("node_modules/reveal.js")).unwrap();
+
+        fs::write(
+            deck.join("package.json"),
+            r#"{ "dependencies": { "reveal.js": "^3.9.2" }, "devDependencies": { "webpack": "5.0.0" } }"#,
+        )
+        .unwrap();
+        fs::write(
+            deck.join("index.html"),
+            r#"<div class="reveal"><div class="slides">
+                <section data-markdown="slides/intro.md"></section>
+            </div></div>
+            <script src="dist/reveal.js"></script>
+            <script>Reveal.initialize({ plugins: [RevealMarkdown, RevealNotes] });</script>"#,
+        )
+        .unwrap();
+        fs::write(deck.join("slides/intro.md"), "# Intro").unwrap();
+        fs::write(deck.join("README.md"), "# Readme").unwrap();
+        fs::write(deck.join("node_modules/reveal.js/notes.md"), "").unwrap();
+        fs::write(
+            deck.join("main.js"),
+            "import RevealZoom from 'reveal.js/plugin/zoom/zoom.esm.js'",
+        )
+        .unwrap();
+
+        let info = inspect(deck).unwrap();
+
+        assert_eq!(info.reveal_version.as_deref(), Some("^3.9.2"));
+        assert_eq!(info.bundlers, vec!["webpack"]);
+        assert_eq!(
+            info.plugins,
+            vec!["RevealMarkdown", "RevealNotes", "RevealZoom"]
+        );
+        assert_eq!(info.slide_files, vec!["slides/intro.md"]);
+        assert_eq!(info.inline_sections, 1);
+
+        let problems = info.incompatibilities.join("\n");
+        assert!(problems.contains("reveal.js ^3.9.2"));
+        assert!(problems.contains("webpack"));
+        assert!(problems.contains("dist/"));
+    }
+
+    #[test]
+    fn test_add_packages_keeps_existing_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{ "name": "talk", "dependencies": { "reveal.js": "^4.6.0" }, "scripts": { "build": "vite build --base ./" } }"#,
+        )
+        .unwrap();
+
+        let added = add_packages(dir.path(), "talk").unwrap();
+        assert!(added.contains(&"vite".to_owned()));
+        assert!(!added.contains(&"reveal.js".to_owned()));
+        assert!(!added.contains(&"script build".to_owned()));
+
+        let package_json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("package.json")).unwrap())
+                .unwrap();
+        assert_eq!(package_json["dependencies"]["reveal.js"], "^4.6.0");
+        assert_eq!(package_json["scripts"]["build"], "vite build --base ./");
+        assert_eq!(package_json["devDependencies"]["vite"], "5.4.21");
+
+        assert!(ensure_gitignore(dir.path()).unwrap());
+        assert!(!ensure_gitignore(dir.path()).unwrap());
+    }
+}
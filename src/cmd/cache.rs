@@ -0,0 +1,77 @@
+use std::{error::Error, sync::atomic::AtomicBool};
+
+use clap::{Args, Subcommand};
+
+use crate::{
+    cmd::init,
+    package::PackageManifest,
+    pm::{self, PackageManager},
+    template,
+};
+
+/// Options for the `oseda cache` command
+#[derive(Args, Debug)]
+pub struct CacheOptions {
+    #[command(subcommand)]
+    pub action: CacheAction,
+}
+
+/// What to do with the package cache `oseda init --offline` installs from
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// Download the dependencies of the templates, so `oseda init --offline` works without network
+    Warm(CacheWarmOptions),
+}
+
+/// Options for `oseda cache warm`
+#[derive(Args, Debug)]
+pub struct CacheWarmOptions {
+    /// Only cache this template's dependencies, every template is cached by default
+    #[arg(long)]
+    pub template: Option<String>,
+
+    /// Package manager to fill the cache of, defaults to the first of npm, pnpm, yarn and bun that is installed
+    #[arg(long)]
+    pub package_manager: Option<PackageManager>,
+}
+
+/// Manages the package cache
+///
+/// # Arguments
+/// * `opts` - options parsed from CLI flags
+///
+/// # Returns
+/// * `Ok(())` if the cache was filled
+/// * `Err` if a template can't be found, or an install failed
+pub fn cache(opts: CacheOptions) -> Result<(), Box<dyn Error>> {
+    match opts.action {
+        CacheAction::Warm(warm) => warm_cache(warm),
+    }
+}
+
+/// Installs each template's package.json in a throwaway project, which fills the cache
+fn warm_cache(opts: CacheWarmOptions) -> Result<(), Box<dyn Error>> {
+    let package_manager = opts
+        .package_manager
+        .unwrap_or_else(pm::default_for_new_project);
+
+    let templates = match &opts.template {
+        Some(spec) => vec![template::resolve(spec)?],
+        None => template::list()?,
+    };
+
+    for template in templates {
+        println!("Caching the dependencies of {}...", template.manifest.name);
+
+        let dir = tempfile::Builder::new().prefix("oseda-cache-").tempdir()?;
+        PackageManifest::for_template(&template.manifest)
+            .write_package_json(dir.path(), "oseda-cache-warm")?;
+        init::install_dependencies(dir.path(), package_manager, false, &AtomicBool::new(false))?;
+    }
+
+    if let Some(cache_dir) = pm::package_cache_dir(package_manager) {
+        println!("Packages cached in {}", cache_dir.display());
+    }
+
+    Ok(())
+}
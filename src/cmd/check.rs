@@ -1,12 +1,15 @@
-use std::time::Duration;
+use std::{path::Path, time::Duration};
 
 use clap::Args;
 use reqwest::StatusCode;
 
+use crate::chapters;
 use crate::cmd::run;
 use crate::config;
+use crate::reveal;
 
 use crate::net::{self, kill_port};
+use crate::pm;
 
 /// Options for the `oseda check` command
 #[derive(Args, Debug)]
@@ -15,6 +18,11 @@ pub struct CheckOptions {
     /// This is only useful if you have changed the default port that Oseda projects run on my default (3000)
     #[arg(long, default_value_t = 3000)]
     port: u16,
+
+    /// Only check the project files, without running the presentation or going over the network
+    /// Fast enough to run before every commit, see `oseda hooks install`
+    #[arg(long)]
+    static_only: bool,
 }
 /// All common error types that could cause `oseda check` to fail
 #[derive(Debug)]
@@ -25,6 +33,8 @@ pub enum OsedaCheckError {
     DirectoryNameMismatch(String),
     CouldNotPingLocalPresentation(String),
     MissingDescription(String),
+    MissingChapters(String),
+    MissingPluginPackages(String),
 }
 
 impl std::error::Error for OsedaCheckError {}
@@ -45,6 +55,10 @@ impl std::fmt::Display for OsedaCheckError {
             Self::MissingDescription(msg) => {
                 write!(f, "Config file is missing description {}", msg)
             }
+            Self::MissingChapters(msg) => write!(f, "Chapters are missing from slides/ {}", msg),
+            Self::MissingPluginPackages(msg) => {
+                write!(f, "Plugin packages are not installed {}", msg)
+            }
         }
     }
 }
@@ -60,7 +74,7 @@ impl std::fmt::Display for OsedaCheckError {
 pub fn check(opts: CheckOptions) -> Result<(), OsedaCheckError> {
     // separate abstraction layer here, want the primary subcommand to call this
     // verify can also be called from deploy (in theory)
-    match verify_project(opts.port) {
+    match verify_project(opts.port, opts.static_only) {
         OsedaProjectStatus::DeployReady => Ok(()),
         OsedaProjectStatus::NotDeploymentReady(err) => Err(err),
     }
@@ -77,18 +91,56 @@ pub enum OsedaProjectStatus {
 /// # Arguments
 /// * `skip_git` - skips git authorship validation
 /// * `port_num` - the port to check for the running project (defaults to 3000)
+/// * `static_only` - stop after the config checks, without running the project.
+///   The author is only checked against `github.login`, nothing goes over the network
 ///
 /// # Returns
 /// * `OsedaProjectStatus::DeployReady` if the project passes all checks
 /// * `OsedaProjectStatus::NotDeploymentReady(err)` if something fails that is commonly seen
-fn verify_project(port_num: u16) -> OsedaProjectStatus {
+fn verify_project(port_num: u16, static_only: bool) -> OsedaProjectStatus {
     // TODO: document me -> assumes working directory is the project folder
 
-    let _conf = match config::read_and_validate_config() {
+    // static checks run before every commit, so they must not wait on the git host
+    let validated = if static_only {
+        config::read_and_validate_config_offline()
+    } else {
+        config::read_and_validate_config()
+    };
+    let conf = match validated {
         Ok(conf) => conf,
         Err(err) => return OsedaProjectStatus::NotDeploymentReady(err),
     };
 
+    let plan = chapters::plan(
+        &chapters::slide_files(Path::new(".")).unwrap_or_default(),
+        &conf.chapters,
+    );
+    for orphan in &plan.orphaned {
+        println!("Warning: slides/{orphan} is not listed in chapters, it won't be presented");
+    }
+    if !plan.missing.is_empty() {
+        return OsedaProjectStatus::NotDeploymentReady(OsedaCheckError::MissingChapters(
+            plan.missing.join(", "),
+        ));
+    }
+
+    if let Some(reveal) = &conf.reveal {
+        let missing = reveal::missing_packages(Path::new("."), reveal);
+        if !missing.is_empty() {
+            return OsedaProjectStatus::NotDeploymentReady(OsedaCheckError::MissingPluginPackages(
+                format!(
+                    "{}, run `{} install`",
+                    missing.join(", "),
+                    pm::detect(Path::new("."), Some(&conf)).program()
+                ),
+            ));
+        }
+    }
+
+    if static_only {
+        return OsedaProjectStatus::DeployReady;
+    }
+
     let _run_handle = std::thread::spawn(run::run);
 
     std::thread::sleep(Duration::from_millis(10000));
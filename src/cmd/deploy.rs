@@ -1,146 +1,679 @@
-use std::{env, error::Error, fs, path::Path};
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use clap::Args;
 
 use crate::{
+    chapters,
+    cmd::upgrade,
     config,
-    github::{self, git},
+    config::{Contributor, OsedaConfig, SignMode},
+    git::{GitRunner, SystemGit, NETWORK_TIMEOUT},
+    identity,
+    library::{self, LibraryConfig},
+    reveal, theme,
 };
 
 /// Options for the `oseda deploy` command
 #[derive(Args, Debug)]
 pub struct DeployOptions {
-    fork_url: String,
-}
+    /// URL of your fork of the library, defaults to the fork saved by `oseda fork --api`
+    fork_url: Option<String>,
 
-struct SshUrl(String);
+    /// Branch to push the course to on your fork, defaults to `course/<title>`
+    #[arg(long, conflicts_with = "all")]
+    branch: Option<String>,
 
-/// string deref
-impl std::ops::Deref for SshUrl {
-    type Target = String;
+    /// Deploy every Oseda project found below the working directory
+    #[arg(long)]
+    all: bool,
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+    /// With --all, open a pull request for each deployed course
+    #[arg(long, requires = "all")]
+    pr: bool,
+
+    /// Sign deploy commits with your git signing key (gpg or ssh), overrides `deploy.sign` in the config
+    #[arg(long)]
+    sign: Option<SignMode>,
 }
 
-/// Convert a standard HTTPS GitHub URL to SSH format
+/// Deploys an Oseda project to the provided fork URL
 ///
 /// # Arguments
-/// * `value` - a String starting with `https://github.com/...`
+/// * `opts` - options with the `fork_url` for the deployment target
 ///
 /// # Returns
-/// * `Ok(SshUrl)` if parsing succeeds
-/// * `Err` if the format is not recognized
-impl TryFrom<String> for SshUrl {
-    type Error = Box<dyn Error>;
+/// * `Ok(())` on success
+/// * `Err` if any git, file, or config step fails, including a check failure
+pub fn deploy(opts: DeployOptions) -> Result<(), Box<dyn Error>> {
+    if opts.all {
+        return deploy_all(opts);
+    }
+
+    // bails if config is bad, before anything gets cloned
+    let conf = config::read_and_validate_config()?;
+    let library = library::resolve(Some(&conf))?;
 
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        // https://github.com/ReeseHatfield/oseda-lib-testing/
-        // into
-        // git@github.com:ReeseHatfield/oseda-lib-testing.git
-        let suffix = value
-            .strip_prefix("https://github.com/")
-            .ok_or("Could not get SSH URL")?;
+    let tmp_dir = tempfile::tempdir()?;
+    let repo_path = tmp_dir.path();
+
+    let fork_url = resolve_fork_url(opts.fork_url, Some(&conf))?;
+
+    let git = SystemGit::new().with_network_timeout(NETWORK_TIMEOUT);
+    sparse_clone(&git, repo_path, &library, &fork_url, &library.courses_path)?;
+
+    // the same name deploy --all, status and undeploy use, whatever the directory is called
+    let course_name = conf.title.clone();
+
+    // every course gets its own branch so each one ends up in an independent PR
+    let branch = opts
+        .branch
+        .unwrap_or_else(|| course_branch_name(&course_name));
 
-        Ok(SshUrl(format!(
-            "git@github.com:{}.git",
-            suffix.trim_end_matches('/')
-        )))
+    let sign = opts.sign.or_else(|| configured_sign(&conf));
+
+    let committed = commit_course(
+        &git,
+        repo_path,
+        &library,
+        &env::current_dir()?,
+        &course_name,
+        conf,
+        &branch,
+        sign,
+    )?;
+
+    if !committed {
+        return Ok(());
     }
+
+    git.run(repo_path, &["push", "-u", "origin", &branch])?;
+
+    println!("Project successfully pushed to branch {branch} on remote.");
+
+    println!(
+        "Add your presentation to {} by making a Pull Request at:",
+        library.web_url()
+    );
+    open_pull_request(&library, &fork_url, &branch)
 }
 
-/// Deploys an Oseda project to the provided fork URL
+/// Deploys every Oseda project below the working directory with a single clone and push
+///
+/// Every course is validated before anything is cloned, then committed on its own
+/// branch so each one can still get an independent PR
 ///
 /// # Arguments
 /// * `opts` - options with the `fork_url` for the deployment target
 ///
 /// # Returns
 /// * `Ok(())` on success
-/// * `Err` if any git, file, or config step fails, including a check failure
-pub fn deploy(opts: DeployOptions) -> Result<(), Box<dyn Error>> {
+/// * `Err` if any course fails validation, the courses target different libraries,
+///   or any git step fails
+fn deploy_all(opts: DeployOptions) -> Result<(), Box<dyn Error>> {
+    let root = env::current_dir()?;
+    let course_dirs = find_courses(&root)?;
+
+    if course_dirs.is_empty() {
+        return Err(format!("No oseda-config.json found below {}", root.display()).into());
+    }
+
+    let mut courses = Vec::new();
+    let mut failures = Vec::new();
+
+    for dir in course_dirs {
+        match config::read_and_validate_config_in(&dir) {
+            Ok(conf) => courses.push((dir, conf)),
+            Err(err) => failures.push(format!("{}: {err}", dir.display())),
+        }
+    }
+
+    if !failures.is_empty() {
+        for failure in &failures {
+            eprintln!("{failure}");
+        }
+        return Err(format!("{} course(s) failed validation", failures.len()).into());
+    }
+
+    println!("Found {} courses to deploy", courses.len());
+
+    let library = library::resolve(Some(&courses[0].1))?;
+    for (dir, conf) in &courses[1..] {
+        if library::resolve(Some(conf))? != library {
+            return Err(format!(
+                "{} deploys to a different library, deploy it on its own",
+                dir.display()
+            )
+            .into());
+        }
+    }
+
+    let saved_fork = courses
+        .iter()
+        .find_map(|(_, c)| c.deploy.as_ref()?.fork_url.clone());
+    let fork_url = resolve_fork_url(opts.fork_url.or(saved_fork), None)?;
+
     let tmp_dir = tempfile::tempdir()?;
     let repo_path = tmp_dir.path();
 
-    let ssh_url: SshUrl = opts.fork_url.try_into()?;
+    let git = SystemGit::new().with_network_timeout(NETWORK_TIMEOUT);
+    sparse_clone(&git, repo_path, &library, &fork_url, &library.courses_path)?;
+
+    let mut branches = Vec::new();
+    for (dir, conf) in courses {
+        let course_name = conf.title.clone();
+        let branch = course_branch_name(&course_name);
+        let sign = opts.sign.or_else(|| configured_sign(&conf));
+
+        if commit_course(
+            &git,
+            repo_path,
+            &library,
+            &dir,
+            &course_name,
+            conf,
+            &branch,
+            sign,
+        )? {
+            branches.push(branch);
+        }
+    }
+
+    if branches.is_empty() {
+        println!("No course changed since it was last deployed, nothing to push.");
+        return Ok(());
+    }
+
+    let mut push_args = vec!["push", "-u", "origin"];
+    push_args.extend(branches.iter().map(String::as_str));
+    git.run(repo_path, &push_args)?;
 
-    git(
+    println!("Pushed {} course branches to remote.", branches.len());
+
+    for branch in &branches {
+        if opts.pr {
+            open_pull_request(&library, &fork_url, branch)?;
+        } else {
+            println!();
+            println!("{}", pull_request_url(&library, &fork_url, branch)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds every Oseda project below `root`, sorted by path
+///
+/// Directories that never hold courses (`.git`, `node_modules`, `dist`, hidden ones)
+/// are skipped, and a project's own subdirectories are not searched
+///
+/// # Returns
+/// * `Ok(Vec<PathBuf>)` with the project directories
+/// * `Err` if a directory cannot be read
+pub fn find_courses(root: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if root.join("oseda-config.json").is_file() {
+        return Ok(vec![root.to_path_buf()]);
+    }
+
+    let mut courses = Vec::new();
+
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        if !entry.file_type()?.is_dir()
+            || name.starts_with('.')
+            || name == "node_modules"
+            || name == "dist"
+        {
+            continue;
+        }
+
+        courses.extend(find_courses(&entry.path())?);
+    }
+
+    courses.sort();
+    Ok(courses)
+}
+
+/// Commits a single course on its branch of the cloned fork
+///
+/// # Arguments
+/// * `repo_path` - path to the cloned fork
+/// * `library` - the library the fork belongs to
+/// * `project_dir` - the project being deployed
+/// * `course_name` - name of the course directory in the library
+/// * `conf` - the project's config
+/// * `branch` - the branch to commit on
+/// * `sign` - how to sign the commit, if at all
+///
+/// # Returns
+/// * `Ok(true)` if a commit was made
+/// * `Ok(false)` if the course is identical to what is on the branch
+/// * `Err` if any git or file step fails
+#[allow(clippy::too_many_arguments)]
+fn commit_course(
+    git: &dyn GitRunner,
+    repo_path: &Path,
+    library: &LibraryConfig,
+    project_dir: &Path,
+    course_name: &str,
+    conf: OsedaConfig,
+    branch: &str,
+    sign: Option<SignMode>,
+) -> Result<bool, Box<dyn Error>> {
+    checkout_course_branch(git, repo_path, branch, "origin/HEAD")?;
+
+    let contributors = conf.contributors.clone();
+
+    let course_path = library.course_path(course_name);
+    let already_deployed = repo_path.join(&course_path).exists();
+
+    let changed = stage_course(git, repo_path, &course_path, project_dir, conf)?;
+
+    if git
+        .stdout(repo_path, &["status", "--porcelain"])?
+        .is_empty()
+    {
+        println!("{course_name} has not changed since it was last deployed, nothing to push.");
+        return Ok(false);
+    }
+
+    if !changed {
+        println!(
+            "Only the config of {course_name} changed, keeping the previous last_updated time"
+        );
+    }
+
+    let message = if already_deployed {
+        format!("Update course {course_name}")
+    } else {
+        format!("Add course {course_name}")
+    };
+
+    println!("Committing {course_name}...");
+    let args = commit_args(&message, &contributors, sign);
+    git.run(
         repo_path,
-        &["clone", "--no-checkout", ssh_url.0.as_str(), "."],
+        &args.iter().map(String::as_str).collect::<Vec<_>>(),
     )?;
 
-    println!("Running git with sparse checkout");
-    git(repo_path, &["sparse-checkout", "init", "--cone"])?;
-    git(repo_path, &["sparse-checkout", "set", "courses"])?;
-    git(repo_path, &["checkout"])?;
+    Ok(true)
+}
+
+/// Gets the signing mode set under `deploy.sign` in a project config
+pub(crate) fn configured_sign(conf: &OsedaConfig) -> Option<SignMode> {
+    conf.deploy.as_ref()?.sign
+}
 
-    let course_name = get_current_dir_name()?;
-    let new_course_dir = repo_path.join("courses").join(&course_name);
+/// Builds the git arguments for a deploy commit
+///
+/// Contributors are added as `Co-authored-by:` trailers. Signing uses whatever key
+/// the user's git config points at, only the signature format is chosen here
+///
+/// # Arguments
+/// * `message` - the commit subject
+/// * `contributors` - the course contributors from the config
+/// * `sign` - how to sign the commit, if at all
+///
+/// # Returns
+/// * the arguments to pass to git -> e.g. `["-c", "gpg.format=ssh", "commit", "-S", "-m", ...]`
+pub(crate) fn commit_args(
+    message: &str,
+    contributors: &[Contributor],
+    sign: Option<SignMode>,
+) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if let Some(mode) = sign {
+        let format = match mode {
+            SignMode::Gpg => "openpgp",
+            SignMode::Ssh => "ssh",
+        };
+        args.extend(["-c".to_owned(), format!("gpg.format={}", format)]);
+    }
 
-    copy_dir_all(env::current_dir()?, &new_course_dir)?;
+    args.push("commit".to_owned());
 
-    // bails if config is bad
-    //
-    // force a no-skip-git
-    let conf = config::read_and_validate_config()?;
+    if sign.is_some() {
+        args.push("-S".to_owned());
+    }
+
+    let mut full_message = message.to_owned();
+    if !contributors.is_empty() {
+        full_message.push_str("\n\n");
+        let trailers: Vec<String> = contributors
+            .iter()
+            .map(|c| format!("Co-authored-by: {} <{}>", c.name, c.email))
+            .collect();
+        full_message.push_str(&trailers.join("\n"));
+    }
 
-    config::update_time(conf)?;
-    println!("Committing files to remote...");
-    git(repo_path, &["add", "."])?;
-    git(repo_path, &["commit", "-m", "Add new course"])?;
-    git(repo_path, &["push"])?;
+    args.extend(["-m".to_owned(), full_message]);
+    args
+}
 
-    println!("Project successfully pushed to remote.");
+/// Picks the fork to deploy to, an explicit URL wins over the one saved in the config
+///
+/// # Returns
+/// * `Ok(String)` with the fork URL
+/// * `Err` if neither is set
+pub(crate) fn resolve_fork_url(
+    fork_url: Option<String>,
+    conf: Option<&config::OsedaConfig>,
+) -> Result<String, Box<dyn Error>> {
+    fork_url
+        .or_else(|| conf.and_then(|c| c.deploy.as_ref()?.fork_url.clone()))
+        .ok_or_else(|| {
+            "No fork URL provided. Pass your fork's URL or run `oseda fork --api` first".into()
+        })
+}
 
-    // https://github.com/oseda-dev/oseda-lib/compare/main...ReeseHatfield:oseda-lib:main?expand=1
+/// Clones the fork into `repo_path` with only `sparse_path` checked out
+///
+/// The library holds every course, so this avoids downloading all of them
+///
+/// # Arguments
+/// * `repo_path` - an empty directory to clone into
+/// * `library` - the library the fork belongs to, used to build the SSH url
+/// * `fork_url` - https URL of the fork, cloned over SSH. Any other URL git understands is used as is
+/// * `sparse_path` - the path inside the repository to check out -> e.g. `courses`
+///
+/// # Returns
+/// * `Ok(())` once the fork is checked out
+/// * `Err` if the URL is invalid or any git step fails
+pub(crate) fn sparse_clone(
+    git: &dyn GitRunner,
+    repo_path: &Path,
+    library: &LibraryConfig,
+    fork_url: &str,
+    sparse_path: &str,
+) -> Result<(), Box<dyn Error>> {
+    let clone_url = if fork_url.starts_with("https://") {
+        library.ssh_url(fork_url)?
+    } else {
+        fork_url.to_owned()
+    };
+
+    println!("Cloning {clone_url}...");
+    git.run(repo_path, &["clone", "--no-checkout", &clone_url, "."])?;
 
-    match github::get_config_from_user_git("user.name") {
-        Some(github_username) => {
-            let pull_request_url = format!(
-                "https://github.com/oseda-dev/oseda-lib/compare/main...{}:oseda-lib:main?expand=1",
-                github_username
-            );
+    println!("Running git with sparse checkout");
+    git.run(repo_path, &["sparse-checkout", "init", "--cone"])?;
+    git.run(repo_path, &["sparse-checkout", "set", sparse_path])?;
+    git.run(repo_path, &["checkout"])?;
 
-            println!("Add your presentation to oseda.net by making a Pull Request at:");
-            println!();
-            println!("{}", pull_request_url);
+    Ok(())
+}
 
-            if open::that(pull_request_url.clone()).is_err() {
-                return Err(format!("Please visit {pull_request_url} in a browser and submit a pull-request by hand").into());
-            };
+/// Builds the page to create a pull request from a branch on the fork
+///
+/// # Arguments
+/// * `library` - the upstream library the pull request targets
+/// * `fork_url` - https URL of the fork
+/// * `branch` - the branch on the fork with the changes
+///
+/// # Returns
+/// * `Ok(String)` with the URL
+/// * `Err` if the github username is missing
+pub(crate) fn pull_request_url(
+    library: &LibraryConfig,
+    fork_url: &str,
+    branch: &str,
+) -> Result<String, Box<dyn Error>> {
+    // https://github.com/oseda-dev/oseda-lib/compare/main...ReeseHatfield:oseda-lib:course/IntroToRust?expand=1
+
+    // the fork url says who owns it, only ask who the user is when it can't be parsed
+    if let Some((fork_owner, fork_repo)) = library::owner_and_repo(fork_url) {
+        return Ok(library.compare_url(&fork_owner, &fork_repo, branch));
+    }
 
-        }
-        None => {
-            println!("Error: could not get github username");
-            return Err("Deployment failed due to missing github credential. Pleas ensure user.name matches your github username".into());
-        }
+    let cwd = std::env::current_dir()?;
+    match identity::github_login(&cwd, library) {
+        Some(identity) => Ok(library.compare_url(&identity.login, &library.repo, branch)),
+        None => Err(format!(
+            "Could not determine your GitHub username for the pull request. {}",
+            identity::login_hint()
+        )
+        .into()),
     }
+}
+
+/// Prints and opens the page to create a pull request from a branch on the fork
+///
+/// # Returns
+/// * `Ok(())` if the page was opened in a browser
+/// * `Err` if the github username is missing, or the browser could not be opened
+pub(crate) fn open_pull_request(
+    library: &LibraryConfig,
+    fork_url: &str,
+    branch: &str,
+) -> Result<(), Box<dyn Error>> {
+    let pull_request_url = pull_request_url(library, fork_url, branch)?;
+
+    println!();
+    println!("{}", pull_request_url);
+
+    if open::that(pull_request_url.clone()).is_err() {
+        return Err(format!(
+            "Please visit {pull_request_url} in a browser and submit a pull-request by hand"
+        )
+        .into());
+    };
 
     Ok(())
 }
 
-/// Util fn to get the current working directory name
+/// Replaces the course directory in the cloned fork with the project and stages it
+///
+/// The copied `oseda-config.json` is stamped for the deployment, the project's own
+/// config is never modified
+///
+/// # Arguments
+/// * `repo_path` - path to the cloned fork, already on the course branch
+/// * `course_path` - path of the course inside the fork -> e.g. `courses/IntroToRust`
+/// * `project_dir` - the project being deployed
+/// * `conf` - the project's config
+///
+/// # Returns
+/// * `Ok(true)` if the deployable content changed since the previous deployment
+/// * `Ok(false)` if it is identical
+/// * `Err` if copying, hashing or staging fails
+pub(crate) fn stage_course(
+    git: &dyn GitRunner,
+    repo_path: &Path,
+    course_path: &str,
+    project_dir: &Path,
+    mut conf: OsedaConfig,
+) -> Result<bool, Box<dyn Error>> {
+    let course_dir = repo_path.join(course_path);
+
+    let previous = config::read_config_file(course_dir.join("oseda-config.json")).ok();
+
+    // start from scratch so files deleted locally are also deleted in the fork
+    if course_dir.exists() {
+        fs::remove_dir_all(&course_dir)?;
+    }
+    copy_dir_all(project_dir, &course_dir)?;
+    // the deployed theme always matches the deployed color, even if the project was never run
+    theme::sync_theme(&course_dir, &conf)?;
+    chapters::sync_slides(&course_dir, &conf)?;
+    reveal::sync_reveal_config(&course_dir, &conf)?;
+
+    let hash = content_hash(git, repo_path, course_path, &conf)?;
+    let changed = config::stamp_deployment(&mut conf, hash, previous.as_ref());
+
+    config::write_config(&course_dir, &conf)?;
+    git.run(repo_path, &["add", "--all", "--", course_path])?;
+
+    Ok(changed)
+}
+
+/// Hashes the deployable content of a staged course
+///
+/// Staging first means the course's `.gitignore` is honored exactly like in the
+/// commit. The config is hashed without `last_updated` and `content_hash`, so only
+/// real edits count
+///
+/// # Returns
+/// * `Ok(String)` with the git object hash of the content listing
+/// * `Err` if any git step fails
+fn content_hash(
+    git: &dyn GitRunner,
+    repo_path: &Path,
+    course_path: &str,
+    conf: &OsedaConfig,
+) -> Result<String, Box<dyn Error>> {
+    git.run(repo_path, &["add", "--all", "--", course_path])?;
+
+    let config_path = format!("{}/oseda-config.json", course_path);
+    let staged = git.stdout(repo_path, &["ls-files", "--stage", "--", course_path])?;
+
+    let mut listing: String = staged
+        .lines()
+        .filter(|line| !line.ends_with(&format!("\t{}", config_path)))
+        .map(|line| format!("{}\n", line.replace(course_path, "")))
+        .collect();
+
+    let mut normalized = serde_json::to_value(conf)?;
+    if let Some(fields) = normalized.as_object_mut() {
+        fields.remove("last_updated");
+        fields.remove("content_hash");
+    }
+    listing.push_str(&normalized.to_string());
+
+    let listing_file = tempfile::NamedTempFile::new()?;
+    fs::write(listing_file.path(), listing)?;
+
+    let listing_path = listing_file.path().to_string_lossy().to_string();
+    let hash = git.stdout(repo_path, &["hash-object", &listing_path])?;
+
+    Ok(hash.trim().to_owned())
+}
+
+/// Builds the default feature branch name for a course -> e.g. `course/IntroToRust`
+///
+/// Characters git refuses in a ref name are replaced with `-`
+///
+/// # Arguments
+/// * `title` - the course title, same as the project directory name
+///
+/// # Returns
+/// * the branch name to deploy the course on
+pub fn course_branch_name(title: &str) -> String {
+    format!("course/{}", ref_safe(title))
+}
+
+/// Builds the default branch name for removing a course -> e.g. `remove/IntroToRust`
+///
+/// Kept apart from `course_branch_name`, so removing a course never lands on its
+/// deploy branch and turns a pull request that is still open into a deletion
+///
+/// # Arguments
+/// * `title` - the course title
+///
+/// # Returns
+/// * the branch name to push the removal to
+pub fn removal_branch_name(title: &str) -> String {
+    format!("remove/{}", ref_safe(title))
+}
+
+/// Replaces what git refuses in a ref name with `-` -> e.g. `Intro To Rust` -> `Intro-To-Rust`
+fn ref_safe(title: &str) -> String {
+    let sanitized: String = title
+        .trim()
+        .chars()
+        .map(|c| match c {
+            ' ' | '~' | '^' | ':' | '?' | '*' | '[' | '\\' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect();
+
+    // refs can't contain `..` or end with `.lock` / `.`
+    let sanitized = sanitized.replace("..", "-");
+    let sanitized = sanitized.trim_end_matches(".lock").trim_end_matches('.');
+
+    sanitized.to_owned()
+}
+
+/// Adds the upstream library as the `upstream` remote of a cloned fork and fetches its default branch
+///
+/// # Arguments
+/// * `repo_path` - path to the cloned fork
+/// * `upstream_url` - clone url of the library -> e.g. `git@github.com:oseda-dev/oseda-lib.git`
+/// * `default_branch` - the library's default branch
+///
+/// # Returns
+/// * `Ok(String)` with the fetched ref -> e.g. `refs/remotes/upstream/main`
+/// * `Err` if the remote can't be added or fetched
+pub(crate) fn fetch_upstream(
+    git: &dyn GitRunner,
+    repo_path: &Path,
+    upstream_url: &str,
+    default_branch: &str,
+) -> Result<String, Box<dyn Error>> {
+    git.run(repo_path, &["remote", "add", "upstream", upstream_url])?;
+    git.run(repo_path, &["fetch", "--quiet", "upstream", default_branch])?;
+
+    Ok(format!("refs/remotes/upstream/{}", default_branch))
+}
+
+/// Switches the cloned fork onto the course branch
+///
+/// Reuses the branch if it was pushed by a previous deploy, otherwise it is
+/// created from `base`, never from whatever is checked out
+///
+/// # Arguments
+/// * `repo_path` - path to the cloned fork
+/// * `branch` - the course branch name
+/// * `base` - where a new branch starts -> e.g. `origin/HEAD` or `refs/remotes/upstream/main`
 ///
 /// # Returns
-/// * `Ok(String)` with the directory name
-/// * `Err` if the name failed to be extracted
-fn get_current_dir_name() -> Result<String, Box<dyn Error>> {
-    // this is like really stupid to have this, since
-    // this logic is basically already used in `check`
-    // but really most of that logic should be moved to a config.rs file
-    // but until then, I am just reading the cwd with this
-    let cwd = env::current_dir()?;
-    let name = cwd
-        .file_name()
-        .ok_or("couldn't get directory name")?
-        .to_string_lossy()
-        .to_string();
-    Ok(name)
+/// * `Ok(())` once the branch is checked out
+/// * `Err` if any git step fails
+pub(crate) fn checkout_course_branch(
+    git: &dyn GitRunner,
+    repo_path: &Path,
+    branch: &str,
+    base: &str,
+) -> Result<(), Box<dyn Error>> {
+    let remote_ref = format!("refs/remotes/origin/{}", branch);
+
+    if git.succeeds(
+        repo_path,
+        &["rev-parse", "--verify", "--quiet", &remote_ref],
+    ) {
+        println!("Updating existing branch {branch}");
+        git.run(
+            repo_path,
+            &[
+                "checkout",
+                "-b",
+                branch,
+                "--track",
+                &format!("origin/{}", branch),
+            ],
+        )?;
+    } else {
+        println!("Creating new branch {branch}");
+        git.run(repo_path, &["checkout", "-b", branch, "--no-track", base])?;
+    }
+
+    Ok(())
 }
 
 /// Recursively copy a directory
 /// https://stackoverflow.com/questions/26958489/how-to-copy-a-folder-recursively-in-rust
-fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+pub(crate) fn copy_dir_all(
+    src: impl AsRef<Path>,
+    dst: impl AsRef<Path>,
+) -> Result<(), Box<dyn Error>> {
     let src = src.as_ref();
     let dst = dst.as_ref();
 
@@ -150,8 +683,8 @@ fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<(), Box<
         let entry = entry?;
         let entry_path = entry.path();
 
-        // skip `.git` directory
-        if entry_path.ends_with(".git") {
+        // skip `.git`, and the upgrade metadata in `.oseda`, which only the author's repo needs
+        if entry_path.ends_with(".git") || entry_path.ends_with(upgrade::OSEDA_DIR) {
             continue;
         }
 
@@ -166,3 +699,287 @@ fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<(), Box<
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_course_branch_name() {
+        assert_eq!(course_branch_name("IntroToRust"), "course/IntroToRust");
+        assert_eq!(course_branch_name("Intro To Rust"), "course/Intro-To-Rust");
+        assert_eq!(course_branch_name("a..b.lock"), "course/a-b");
+        assert_eq!(removal_branch_name("Intro To Rust"), "remove/Intro-To-Rust");
+    }
+
+    #[test]
+    fn test_commit_args_plain() {
+        assert_eq!(
+            commit_args("Add course IntroToRust", &[], None),
+            vec!["commit", "-m", "Add course IntroToRust"]
+        );
+    }
+
+    #[test]
+    fn test_commit_args_signed_with_trailers() {
+        let contributors = vec![
+            Contributor {
+                name: "Jane Doe".to_owned(),
+                email: "jane@example.edu".to_owned(),
+            },
+            Contributor {
+                name: "John Roe".to_owned(),
+                email: "john@example.edu".to_owned(),
+            },
+        ];
+
+        let args = commit_args(
+            "Update course IntroToRust",
+            &contributors,
+            Some(SignMode::Ssh),
+        );
+
+        assert_eq!(
+            args,
+            vec![
+                "-c",
+                "gpg.format=ssh",
+                "commit",
+                "-S",
+                "-m",
+                "Update course IntroToRust\n\nCo-authored-by: Jane Doe <jane@example.edu>\nCo-authored-by: John Roe <john@example.edu>"
+            ]
+        );
+    }
+
+    /// git isolated from the user's config, with a fixed identity so commits work anywhere
+    fn test_git() -> SystemGit {
+        SystemGit::new()
+            .with_env("GIT_CONFIG_NOSYSTEM", "1")
+            .with_env("GIT_CONFIG_GLOBAL", "/dev/null")
+            .with_env("GIT_AUTHOR_NAME", "Jane Doe")
+            .with_env("GIT_AUTHOR_EMAIL", "jane@example.edu")
+            .with_env("GIT_COMMITTER_NAME", "Jane Doe")
+            .with_env("GIT_COMMITTER_EMAIL", "jane@example.edu")
+    }
+
+    /// Creates a bare repository standing in for a fork of the library
+    fn bare_fork(git: &SystemGit, root: &Path) -> PathBuf {
+        let fork = root.join("fork.git");
+        let seed = root.join("seed");
+        fs::create_dir_all(&fork).unwrap();
+        fs::create_dir_all(seed.join("courses")).unwrap();
+        fs::write(seed.join("courses/.gitkeep"), "").unwrap();
+
+        git.run(&fork, &["init", "--bare", "--initial-branch=main"])
+            .unwrap();
+        git.run(&seed, &["init", "--initial-branch=main"]).unwrap();
+        git.run(&seed, &["add", "."]).unwrap();
+        git.run(&seed, &["commit", "-m", "init"]).unwrap();
+        git.run(&seed, &["push", fork.to_str().unwrap(), "main"])
+            .unwrap();
+
+        fork
+    }
+
+    fn sample_project(root: &Path) -> (PathBuf, OsedaConfig) {
+        let project = root.join("IntroToRust");
+        fs::create_dir_all(project.join("slides")).unwrap();
+        fs::create_dir_all(project.join("node_modules/vite")).unwrap();
+        fs::write(project.join("slides/slides.md"), "# Welcome").unwrap();
+        fs::write(project.join("node_modules/vite/index.js"), "").unwrap();
+        fs::write(project.join(".gitignore"), "node_modules\n").unwrap();
+        fs::create_dir_all(project.join(".oseda")).unwrap();
+        fs::write(project.join(".oseda/package-base.json"), "{}").unwrap();
+
+        let conf: OsedaConfig = serde_json::from_str(
+            r##"{
+                "title": "IntroToRust",
+                "author": "JaneDoe",
+                "tags": ["ComputerScience"],
+                "last_updated": "2024-07-10T12:34:56Z",
+                "color": "#000000",
+                "description": "Learn rust"
+            }"##,
+        )
+        .unwrap();
+        config::write_config(&project, &conf).unwrap();
+
+        (project, conf)
+    }
+
+    fn deploy_once(git: &SystemGit, root: &Path, fork: &Path, project: &Path) -> bool {
+        let clone = tempfile::tempdir_in(root).unwrap();
+        let library = LibraryConfig::default();
+        let conf = config::read_config_file(project.join("oseda-config.json")).unwrap();
+
+        sparse_clone(
+            git,
+            clone.path(),
+            &library,
+            fork.to_str().unwrap(),
+            &library.courses_path,
+        )
+        .unwrap();
+
+        let committed = commit_course(
+            git,
+            clone.path(),
+            &library,
+            project,
+            "IntroToRust",
+            conf,
+            "course/IntroToRust",
+            None,
+        )
+        .unwrap();
+
+        if committed {
+            git.run(
+                clone.path(),
+                &["push", "-u", "origin", "course/IntroToRust"],
+            )
+            .unwrap();
+        }
+
+        committed
+    }
+
+    #[test]
+    fn test_deploy_to_bare_fork() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+        let git = test_git();
+
+        let fork = bare_fork(&git, root);
+        let (project, _) = sample_project(root);
+
+        assert!(deploy_once(&git, root, &fork, &project));
+
+        let files = git
+            .stdout(
+                &fork,
+                &["ls-tree", "-r", "--name-only", "course/IntroToRust"],
+            )
+            .unwrap();
+        assert!(files.contains("courses/IntroToRust/slides/slides.md"));
+        assert!(files.contains("courses/IntroToRust/oseda-config.json"));
+        assert!(!files.contains("node_modules"));
+        assert!(!files.contains(".oseda"));
+
+        let deployed = git
+            .stdout(
+                &fork,
+                &[
+                    "show",
+                    "course/IntroToRust:courses/IntroToRust/oseda-config.json",
+                ],
+            )
+            .unwrap();
+        let deployed: OsedaConfig = serde_json::from_str(&deployed).unwrap();
+        assert!(deployed.content_hash.is_some());
+
+        // the local config is never touched by a deploy
+        let local = config::read_config_file(project.join("oseda-config.json")).unwrap();
+        assert!(local.content_hash.is_none());
+
+        // nothing changed, so nothing gets committed
+        assert!(!deploy_once(&git, root, &fork, &project));
+
+        fs::write(project.join("slides/slides.md"), "# Welcome back").unwrap();
+        assert!(deploy_once(&git, root, &fork, &project));
+
+        let redeployed = git
+            .stdout(
+                &fork,
+                &[
+                    "show",
+                    "course/IntroToRust:courses/IntroToRust/oseda-config.json",
+                ],
+            )
+            .unwrap();
+        let redeployed: OsedaConfig = serde_json::from_str(&redeployed).unwrap();
+        assert_ne!(redeployed.content_hash, deployed.content_hash);
+        assert!(redeployed.last_updated > deployed.last_updated);
+    }
+
+    #[test]
+    fn test_checkout_course_branch_from_upstream() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+        let git = test_git();
+
+        // the course was merged upstream and its branch deleted, the fork's main never saw it
+        let fork = bare_fork(&git, root);
+        let (project, _) = sample_project(root);
+        assert!(deploy_once(&git, root, &fork, &project));
+        let upstream = root.join("upstream.git");
+        git.run(
+            root,
+            &[
+                "clone",
+                "--bare",
+                "--quiet",
+                fork.to_str().unwrap(),
+                "upstream.git",
+            ],
+        )
+        .unwrap();
+        git.run(&upstream, &["branch", "-f", "main", "course/IntroToRust"])
+            .unwrap();
+        git.run(&fork, &["branch", "-D", "course/IntroToRust"])
+            .unwrap();
+
+        let clone = tempfile::tempdir_in(root).unwrap();
+        let library = LibraryConfig::default();
+        let course_path = library.course_path("IntroToRust");
+        sparse_clone(
+            &git,
+            clone.path(),
+            &library,
+            fork.to_str().unwrap(),
+            &course_path,
+        )
+        .unwrap();
+
+        let upstream_ref =
+            fetch_upstream(&git, clone.path(), upstream.to_str().unwrap(), "main").unwrap();
+        assert_eq!(upstream_ref, "refs/remotes/upstream/main");
+
+        checkout_course_branch(&git, clone.path(), "course/IntroToRust", &upstream_ref).unwrap();
+        assert!(clone
+            .path()
+            .join(&course_path)
+            .join("oseda-config.json")
+            .exists());
+    }
+
+    #[test]
+    fn test_find_courses() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+
+        for course in [
+            "IntroToRust",
+            "nested/Calculus",
+            "node_modules/not-a-course",
+        ] {
+            fs::create_dir_all(root.join(course)).unwrap();
+            fs::write(root.join(course).join("oseda-config.json"), "{}").unwrap();
+        }
+        // courses inside a course are not separate courses
+        fs::create_dir_all(root.join("IntroToRust/slides/extra")).unwrap();
+        fs::write(
+            root.join("IntroToRust/slides/extra/oseda-config.json"),
+            "{}",
+        )
+        .unwrap();
+
+        let courses = find_courses(root).unwrap();
+
+        assert_eq!(
+            courses,
+            vec![root.join("IntroToRust"), root.join("nested/Calculus")]
+        );
+    }
+}
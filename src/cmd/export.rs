@@ -1,5 +1,6 @@
 use std::{
     error::Error,
+    path::Path,
     process::Command,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -9,7 +10,7 @@ use std::{
 
 use clap::Args;
 
-use crate::{cmd::run, net::kill_port};
+use crate::{cmd::run, config, net::kill_port, package::PackageManifest, pm};
 
 /// Options struct for the export subcommand
 #[derive(Args, Debug, Clone)]
@@ -28,8 +29,15 @@ pub fn export(opts: ExportOptions) -> Result<(), Box<dyn Error>> {
         eprintln!("Warning, could not kill value on desired port")
     }
 
-    let output = Command::new("npm")
-        .args(["install", "decktape@3.15.0"])
+    let conf = config::read_config_file("oseda-config.json").ok();
+    let package_manager = pm::detect(Path::new("."), conf.as_ref());
+
+    let decktape = PackageManifest::base()
+        .tool_spec("decktape")
+        .ok_or("decktape is missing from the package manifest")?;
+
+    let output = Command::new(package_manager.program())
+        .args(package_manager.add_args(&[decktape], false))
         .current_dir(".")
         .output()?;
 
@@ -38,7 +46,7 @@ pub fn export(opts: ExportOptions) -> Result<(), Box<dyn Error>> {
             "Decktape installation failure: {}",
             String::from_utf8_lossy(&output.stderr)
         );
-        return Err("npm init failed".into());
+        return Err("decktape install failed".into());
     }
 
     // decktape automatic http://localhost:3000/ Desktop/IntroToRust/slides.pdf
@@ -54,8 +62,9 @@ pub fn export(opts: ExportOptions) -> Result<(), Box<dyn Error>> {
     let addr = format!("http://localhost:{}", opts.port);
 
     // run decktape, assuming the server has spun up by now
-    let export_output = Command::new("decktape")
-        .args(["automatic", &addr, &opts.output])
+    // decktape is a local dependency, so it runs through the package manager
+    let export_output = package_manager
+        .exec("decktape", &["automatic", &addr, &opts.output])
         .output()?;
 
     // send shutdown flag, should signal to run_with_shutdown to kill the process
@@ -68,7 +77,7 @@ pub fn export(opts: ExportOptions) -> Result<(), Box<dyn Error>> {
             "Decktape PDF export failure: {}",
             String::from_utf8_lossy(&export_output.stderr)
         );
-        return Err("npm init failed".into());
+        return Err("decktape export failed".into());
     }
 
     Ok(())
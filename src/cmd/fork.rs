@@ -1,11 +1,124 @@
-use std::error::Error;
+use std::{error::Error, thread, time::Duration};
 
-pub fn fork() -> Result<(), Box<dyn Error>> {
-    let fork_url = "https://github.com/oseda-dev/oseda-lib/fork".to_owned();
+use clap::Args;
+
+use crate::{
+    config::{self, DeployConfig},
+    github::{self, GithubApi},
+    library::{self, LibraryConfig},
+};
+
+/// Options for the `oseda fork` command
+#[derive(Args, Debug)]
+pub struct ForkOptions {
+    /// Create the fork through the GitHub API instead of opening a browser
+    #[arg(long)]
+    pub api: bool,
+
+    /// GitHub token used with --api, defaults to GITHUB_TOKEN or GH_TOKEN
+    #[arg(long, requires = "api")]
+    pub token: Option<String>,
+}
+
+// github forks asynchronously, this is how long we wait for it to show up
+const FORK_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const FORK_POLL_ATTEMPTS: u32 = 30;
+
+/// Forks the library repository, either in the browser or through the GitHub API
+///
+/// # Arguments
+/// * `opts` - options parsed from CLI flags
+///
+/// # Returns
+/// * `Ok(())` if the fork page was opened or the fork is ready
+/// * `Err` if the browser could not be opened, or any API step fails
+pub fn fork(opts: ForkOptions) -> Result<(), Box<dyn Error>> {
+    // a project may point at a self-hosted library, but fork also works outside of one
+    let project_conf = config::read_config_file("oseda-config.json").ok();
+    let library = library::resolve(project_conf.as_ref())?;
+
+    if opts.api {
+        let token = opts
+            .token
+            .or_else(github::token_from_env)
+            .ok_or("No GitHub token found. Pass --token or set GITHUB_TOKEN")?;
+
+        let clone_url = fork_with_api(&library, token)?;
+
+        println!("Clone URL: {clone_url}");
+
+        match project_conf {
+            Some(mut conf) => {
+                conf.deploy
+                    .get_or_insert_with(DeployConfig::default)
+                    .fork_url = Some(clone_url);
+                config::write_config(".", &conf)?;
+                println!("Saved fork as the deploy target in oseda-config.json");
+            }
+            None => println!("Not in an oseda project, pass this URL to `oseda deploy`"),
+        }
+
+        return Ok(());
+    }
+
+    let fork_url = library.fork_url();
 
     open::that(fork_url.clone()).map_err(|_| {
-        format!("Please visit {fork_url} in a browser and fork the oseda-lib repository")
+        format!(
+            "Please visit {fork_url} in a browser and fork the {} repository",
+            library.repo
+        )
     })?;
 
     Ok(())
 }
+
+/// Creates a fork of the library through the GitHub API and waits until it can be cloned
+///
+/// # Returns
+/// * `Ok(String)` with the clone URL of the fork, whether it was just created or already existed
+/// * `Err` if the token is rejected, an unrelated repository is in the way, or the fork never shows up
+fn fork_with_api(library: &LibraryConfig, token: String) -> Result<String, Box<dyn Error>> {
+    let api = GithubApi::new(library, token)?;
+    let login = api.login()?;
+    let upstream = format!("{}/{}", library.owner, library.repo);
+
+    if let Some(existing) = api.get_repo(&login, &library.repo)? {
+        let is_upstream_fork = existing.fork
+            && existing
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.full_name.eq_ignore_ascii_case(&upstream));
+
+        if !is_upstream_fork {
+            return Err(format!(
+                "{} already exists but is not a fork of {upstream}",
+                existing.full_name
+            )
+            .into());
+        }
+
+        println!(
+            "A fork of {upstream} already exists at {}",
+            existing.html_url
+        );
+        return Ok(existing.clone_url);
+    }
+
+    println!("Forking {upstream} as {login}...");
+    let fork = api.create_fork(&library.owner, &library.repo)?;
+
+    for _ in 0..FORK_POLL_ATTEMPTS {
+        if api.branch_exists(&fork.full_name, &fork.default_branch)? {
+            println!("Created fork {}", fork.html_url);
+            return Ok(fork.clone_url);
+        }
+        thread::sleep(FORK_POLL_INTERVAL);
+    }
+
+    Err(format!(
+        "Timed out waiting for {} to be ready, check {} in a browser",
+        fork.full_name, fork.html_url
+    )
+    .into())
+}
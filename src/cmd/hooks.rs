@@ -0,0 +1,397 @@
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Subcommand};
+
+use crate::git::{GitRunner, SystemGit};
+
+/// Options for the `oseda hooks` command
+#[derive(Args, Debug)]
+pub struct HooksOptions {
+    #[command(subcommand)]
+    pub action: HooksAction,
+}
+
+/// What to do with the git hooks of the project repository
+#[derive(Subcommand, Debug)]
+pub enum HooksAction {
+    /// Check the project before every commit and push
+    Install(HooksInstallOptions),
+    /// Remove the hooks installed by oseda, restoring any hooks they replaced
+    Uninstall,
+}
+
+/// Options for `oseda hooks install`
+#[derive(Args, Debug)]
+pub struct HooksInstallOptions {
+    /// Install into this directory and point git's `core.hooksPath` at it,
+    /// e.g. to keep the hooks in the repository
+    #[arg(long)]
+    pub hooks_path: Option<PathBuf>,
+}
+
+/// Hooks oseda installs, and the check each one runs
+const HOOKS: [(&str, &str); 2] = [
+    // pre-commit runs on every commit, keep it fast
+    ("pre-commit", "oseda check --static-only"),
+    ("pre-push", "oseda check"),
+];
+
+/// Marks a hook as written by oseda, so it is never chained to or removed by mistake
+const MARKER: &str = "# installed by oseda, remove with `oseda hooks uninstall`";
+
+/// Remembers the `core.hooksPath` oseda set, uninstall only resets it if it is unchanged
+const HOOKS_PATH_KEY: &str = "oseda.hooksPath";
+
+/// Existing hooks are moved here and run before the oseda check
+const CHAINED_SUFFIX: &str = ".oseda-chained";
+
+/// Installs or removes the oseda git hooks for the project in the working directory
+///
+/// # Arguments
+/// * `opts` - options parsed from CLI flags
+///
+/// # Returns
+/// * `Ok(())` if the hooks were installed or removed
+/// * `Err` if the project is not in a git repository, or a hook could not be written
+pub fn hooks(opts: HooksOptions) -> Result<(), Box<dyn Error>> {
+    let git = SystemGit::new();
+    let cwd = env::current_dir()?;
+
+    if !git.succeeds(&cwd, &["rev-parse", "--git-dir"]) {
+        return Err("Not in a git repository, run `git init` first".into());
+    }
+
+    match opts.action {
+        HooksAction::Install(install) => {
+            // hooks run from the top of the repository, the project may be below it
+            let prefix = git.stdout(&cwd, &["rev-parse", "--show-prefix"])?;
+
+            if let Some(hooks_path) = &install.hooks_path {
+                // git resolves a relative core.hooksPath from the top of the repository
+                let hooks_path = Path::new(prefix.trim()).join(hooks_path);
+                let hooks_path = hooks_path.to_string_lossy();
+                set_hooks_path(&git, &cwd, &hooks_path)?;
+                println!("Set core.hooksPath to {hooks_path}");
+            }
+
+            let hooks_dir = hooks_dir(&git, &cwd)?;
+
+            for installed in install_hooks(&hooks_dir, prefix.trim())? {
+                println!("Installed {}", installed.display());
+            }
+        }
+        HooksAction::Uninstall => {
+            let hooks_dir = hooks_dir(&git, &cwd)?;
+
+            let removed = uninstall_hooks(&hooks_dir)?;
+            if removed.is_empty() {
+                println!("No oseda hooks installed in {}", hooks_dir.display());
+            }
+            for removed in removed {
+                println!("Removed {}", removed.display());
+            }
+
+            if let Some(hooks_path) = unset_hooks_path(&git, &cwd)? {
+                println!("Unset core.hooksPath, it was {hooks_path}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Points `core.hooksPath` at `hooks_path`, and remembers that oseda did
+fn set_hooks_path(git: &dyn GitRunner, cwd: &Path, hooks_path: &str) -> Result<(), Box<dyn Error>> {
+    git.run(cwd, &["config", "core.hooksPath", hooks_path])?;
+    git.run(cwd, &["config", HOOKS_PATH_KEY, hooks_path])?;
+
+    Ok(())
+}
+
+/// Unsets the `core.hooksPath` oseda set, so git runs the hooks in .git/hooks again
+///
+/// # Returns
+/// * `Ok(Some(String))` with the path that was unset
+/// * `Ok(None)` if oseda never set it, or it was changed since
+/// * `Err` if the git config can't be updated
+fn unset_hooks_path(git: &dyn GitRunner, cwd: &Path) -> Result<Option<String>, Box<dyn Error>> {
+    let Ok(set_by_oseda) = git.stdout(cwd, &["config", "--get", HOOKS_PATH_KEY]) else {
+        return Ok(None);
+    };
+    let current = git.stdout(cwd, &["config", "--get", "core.hooksPath"]).ok();
+
+    git.run(cwd, &["config", "--unset", HOOKS_PATH_KEY])?;
+
+    // someone pointed it elsewhere since, that's theirs to keep
+    if current.as_deref().map(str::trim) != Some(set_by_oseda.trim()) {
+        return Ok(None);
+    }
+
+    git.run(cwd, &["config", "--unset", "core.hooksPath"])?;
+    Ok(Some(set_by_oseda.trim().to_owned()))
+}
+
+/// Gets the directory git runs hooks from, honoring `core.hooksPath` and worktrees
+fn hooks_dir(git: &dyn GitRunner, cwd: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let hooks_dir = git.stdout(cwd, &["rev-parse", "--git-path", "hooks"])?;
+    let hooks_dir = cwd.join(hooks_dir.trim());
+
+    // tidies up `../../.git/hooks` for printing, the directory may not exist yet
+    Ok(hooks_dir.canonicalize().unwrap_or(hooks_dir))
+}
+
+/// Writes the oseda hooks, moving aside any hook that is already there
+///
+/// # Arguments
+/// * `hooks_dir` - where git looks for hooks
+/// * `project_prefix` - path of the project inside the repository, empty if it is the top
+///
+/// # Returns
+/// * `Ok(Vec<PathBuf>)` with the hooks that were written
+/// * `Err` if an existing hook can't be moved aside, or a hook can't be written
+pub fn install_hooks(
+    hooks_dir: &Path,
+    project_prefix: &str,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    fs::create_dir_all(hooks_dir)?;
+
+    let mut installed = Vec::new();
+    for (name, check) in HOOKS {
+        let hook_path = hooks_dir.join(name);
+        let chained_path = hooks_dir.join(format!("{name}{CHAINED_SUFFIX}"));
+
+        if hook_path.exists() && !is_oseda_hook(&hook_path) {
+            if chained_path.exists() {
+                return Err(format!(
+                    "{} already exists, cannot keep the current {name} hook",
+                    chained_path.display()
+                )
+                .into());
+            }
+
+            fs::rename(&hook_path, &chained_path)?;
+            println!(
+                "Existing {name} hook moved to {}, it still runs first",
+                chained_path.display()
+            );
+        }
+
+        fs::write(&hook_path, hook_script(name, check, project_prefix))?;
+        make_executable(&hook_path)?;
+
+        installed.push(hook_path);
+    }
+
+    Ok(installed)
+}
+
+/// Removes the oseda hooks and puts back the hooks they chained to
+///
+/// Hooks without the oseda marker are left alone, someone else owns them
+///
+/// # Returns
+/// * `Ok(Vec<PathBuf>)` with the hooks that were removed
+/// * `Err` if a hook could not be removed or restored
+pub fn uninstall_hooks(hooks_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut removed = Vec::new();
+
+    for (name, _) in HOOKS {
+        let hook_path = hooks_dir.join(name);
+        let chained_path = hooks_dir.join(format!("{name}{CHAINED_SUFFIX}"));
+
+        if !hook_path.exists() {
+            continue;
+        }
+
+        if !is_oseda_hook(&hook_path) {
+            println!(
+                "{} was not installed by oseda, leaving it",
+                hook_path.display()
+            );
+            continue;
+        }
+
+        fs::remove_file(&hook_path)?;
+        if chained_path.exists() {
+            fs::rename(&chained_path, &hook_path)?;
+            println!("Restored the previous {name} hook");
+        }
+
+        removed.push(hook_path);
+    }
+
+    Ok(removed)
+}
+
+fn is_oseda_hook(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|script| script.contains(MARKER))
+}
+
+/// Builds the shell script for a hook
+///
+/// The chained hook runs first with the original arguments and stdin,
+/// pre-push reads the refs being pushed from stdin
+fn hook_script(name: &str, check: &str, project_prefix: &str) -> String {
+    format!(
+        r#"#!/bin/sh
+{MARKER}
+
+chained="$0{CHAINED_SUFFIX}"
+if [ -x "$chained" ]; then
+    "$chained" "$@" || exit $?
+fi
+
+project={project}
+cd "$(git rev-parse --show-toplevel)/$project" || exit 1
+
+if ! command -v oseda >/dev/null 2>&1; then
+    echo "oseda {name} hook: oseda is not installed, skipping checks" >&2
+    exit 0
+fi
+
+{check} </dev/null
+"#,
+        project = shell_quote(project_prefix)
+    )
+}
+
+/// Quotes a string for sh, nothing inside single quotes is special but the quote itself
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+// git for windows runs hooks through its own sh, no executable bit needed
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_install_chains_and_uninstall_restores() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks_dir = dir.path().join("hooks");
+        fs::create_dir_all(&hooks_dir).unwrap();
+
+        let existing = "#!/bin/sh\nnpx lint-staged\n";
+        fs::write(hooks_dir.join("pre-commit"), existing).unwrap();
+
+        install_hooks(&hooks_dir, "courses/IntroToRust/").unwrap();
+
+        let pre_commit = fs::read_to_string(hooks_dir.join("pre-commit")).unwrap();
+        assert!(pre_commit.contains(MARKER));
+        assert!(pre_commit.contains("oseda check --static-only"));
+        assert!(pre_commit.contains("project='courses/IntroToRust/'"));
+        assert_eq!(
+            fs::read_to_string(hooks_dir.join("pre-commit.oseda-chained")).unwrap(),
+            existing
+        );
+        assert!(hooks_dir.join("pre-push").exists());
+        assert!(!hooks_dir.join("pre-push.oseda-chained").exists());
+
+        // installing again must not chain oseda to itself
+        install_hooks(&hooks_dir, "courses/IntroToRust/").unwrap();
+        assert_eq!(
+            fs::read_to_string(hooks_dir.join("pre-commit.oseda-chained")).unwrap(),
+            existing
+        );
+
+        let removed = uninstall_hooks(&hooks_dir).unwrap();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(
+            fs::read_to_string(hooks_dir.join("pre-commit")).unwrap(),
+            existing
+        );
+        assert!(!hooks_dir.join("pre-commit.oseda-chained").exists());
+        assert!(!hooks_dir.join("pre-push").exists());
+    }
+
+    #[test]
+    fn test_uninstall_unsets_hooks_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = SystemGit::new();
+        git.run(dir.path(), &["init", "--quiet"]).unwrap();
+
+        set_hooks_path(&git, dir.path(), ".githooks").unwrap();
+        assert_eq!(
+            unset_hooks_path(&git, dir.path()).unwrap().as_deref(),
+            Some(".githooks")
+        );
+        assert!(!git.succeeds(dir.path(), &["config", "--get", "core.hooksPath"]));
+
+        // changed by someone else after install, so it stays
+        set_hooks_path(&git, dir.path(), ".githooks").unwrap();
+        git.run(dir.path(), &["config", "core.hooksPath", ".husky"])
+            .unwrap();
+        assert_eq!(unset_hooks_path(&git, dir.path()).unwrap(), None);
+        assert_eq!(
+            git.stdout(dir.path(), &["config", "--get", "core.hooksPath"])
+                .unwrap()
+                .trim(),
+            ".husky"
+        );
+        assert!(!git.succeeds(dir.path(), &["config", "--get", HOOKS_PATH_KEY]));
+    }
+
+    #[test]
+    fn test_uninstall_leaves_foreign_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("pre-push"), "#!/bin/sh\nexit 0\n").unwrap();
+
+        let removed = uninstall_hooks(dir.path()).unwrap();
+
+        assert!(removed.is_empty());
+        assert!(dir.path().join("pre-push").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hook_script_quotes_project_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let prefix = r#"courses/Intro "Rust" $HOME `id` it's"#;
+        let project = dir.path().join(prefix);
+        fs::create_dir_all(&project).unwrap();
+        std::process::Command::new("git")
+            .args(["init", "--quiet"])
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+
+        // a stand-in oseda, so the hook gets past its install check
+        let bin = tempfile::tempdir().unwrap();
+        fs::write(bin.path().join("oseda"), "#!/bin/sh\n").unwrap();
+        make_executable(&bin.path().join("oseda")).unwrap();
+
+        // pwd stands in for the oseda check, the hook should end up in the project
+        let script = hook_script("pre-commit", "pwd", prefix);
+        let path = format!(
+            "{}:{}",
+            bin.path().display(),
+            std::env::var("PATH").unwrap()
+        );
+        let output = std::process::Command::new("sh")
+            .args(["-c", &script])
+            .current_dir(dir.path())
+            .env("PATH", path)
+            .output()
+            .unwrap();
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.trim_end().ends_with(prefix), "{stdout}");
+    }
+}
@@ -1,18 +1,33 @@
-
 use std::{
+    collections::BTreeMap,
     error::Error,
-    fs::{self},
-    process::Command,
-    str::FromStr,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
 };
 
 use clap::Args;
-use strum::IntoEnumIterator;
 
-use crate::{config, template::Template};
+use crate::{
+    chapters,
+    cmd::upgrade,
+    config::{self, OsedaConfig, TemplateInfo},
+    package::PackageManifest,
+    pm::{self, PackageManager},
+    reveal,
+    template::{self, Template, TemplateFiles, TemplateManifest},
+    theme,
+};
 
 /// Options for the `oseda init` command
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Default)]
 pub struct InitOptions {
     #[arg(long)]
     pub title: Option<String>,
@@ -23,146 +38,488 @@ pub struct InitOptions {
     #[arg(long)]
     pub color: Option<String>,
 
+    /// Template to scaffold from: a name from `oseda template list`, a path to a template directory, or a git URL
     #[arg(long)]
     pub template: Option<String>,
+
+    /// Answer a template prompt without being asked, can be repeated
+    #[arg(long = "var", value_name = "KEY=VALUE")]
+    pub vars: Vec<String>,
+
+    /// Replace the project directory if it already exists and is not empty
+    #[arg(long)]
+    pub force: bool,
+
+    /// Package manager to install with, defaults to the first of npm, pnpm, yarn and bun that is installed
+    #[arg(long)]
+    pub package_manager: Option<PackageManager>,
+
+    /// Only list the dependencies in package.json, install them later with e.g. `npm install`
+    #[arg(long, conflicts_with = "offline")]
+    pub no_install: bool,
+
+    /// Install from oseda's package cache without touching the network, fill it with `oseda cache warm`
+    #[arg(long)]
+    pub offline: bool,
 }
 
-// embed all the static markdown template files into binary
-const MD_VITE_CONFIG_JS: &str = include_str!("../static/md-templates/vite.config.js");
-const MD_INDEX_HTML: &str = include_str!("../static/md-templates/index.html");
-const MD_MAIN_JS: &str = include_str!("../static/md-templates/main.js");
-const MD_SLIDES: &str = include_str!("../static/md-templates/slides.md");
-const MD_CUSTOM_CSS: &str = include_str!("../static/md-templates/custom.css");
-const MD_FERRIS: &[u8] = include_bytes!("../static/md-templates/ferris.png");
-
-const MD_GITIGNORE: &str = include_str!("../static/md-templates/.gitignore");
-
-// do the same with the html templates
-const HTML_VITE_CONFIG_JS: &str = include_str!("../static/html-templates/vite.config.js");
-const HTML_INDEX_HTML: &str = include_str!("../static/html-templates/index.html");
-const HTML_MAIN_JS: &str = include_str!("../static/html-templates/main.js");
-const HTML_SLIDES: &str = include_str!("../static/html-templates/slides.html");
-const HTML_CUSTOM_CSS: &str = include_str!("../static/html-templates/custom.css");
-const HTML_FERRIS: &[u8] = include_bytes!("../static/html-templates/ferris.png");
-const HTML_GITIGNORE: &str = include_str!("../static/html-templates/.gitignore");
+/// How init gets the template dependencies into the project
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstallMode {
+    /// download whatever is missing, filling the package cache
+    Online,
+    /// only use the package cache
+    Offline,
+    /// write package.json and stop there
+    Skip,
+}
 
 /// Initialize an Oseda project with the provided options
 ///
 /// This command will:
-/// - Run `npm init`
-/// - Install required dependencies (Vite, Reveal.js, etc)
-/// - Write config and boilerplate files
+/// - Write a package.json with the pinned dependencies (Vite, Reveal.js, etc) and scripts
+/// - Install them with the chosen package manager
+/// - Write config and the template files
+///
+/// Everything happens in a staging directory next to the project, which is only
+/// moved into place once every step succeeded. Failures and ctrl+c leave nothing behind
 ///
 /// # Arguments
 /// * `_opts` - command-line options (this is unused rn, used later I hope)
 ///
 /// # Returns
 /// * `Ok(())` if project initialization is suceeded
-/// * `Err` if any step (npm, file write, config generation etc) fails
+/// * `Err` if any step (install, file write, config generation etc) fails
 pub fn init(opts: InitOptions) -> Result<(), Box<dyn Error>> {
-    let template = match opts.template {
-        Some(ref arg_template) => {
-            Template::from_str(arg_template).map_err(|_| "Invalid template".to_string())?
+    let (template, template_spec) = match opts.template {
+        Some(ref arg_template) => (template::resolve(arg_template)?, arg_template.clone()),
+        None => {
+            let template = prompt_template()?;
+            let name = template.manifest.name.clone();
+            (template, name)
         }
-        None => prompt_template()?,
     };
 
-    let conf = config::create_conf(opts)?;
+    let values = prompt_values(&template.manifest, &opts.vars)?;
 
-    std::fs::create_dir_all(&conf.title)?;
+    let force = opts.force;
+    let install_mode = if opts.no_install {
+        InstallMode::Skip
+    } else if opts.offline {
+        InstallMode::Offline
+    } else {
+        InstallMode::Online
+    };
+    let package_manager = opts
+        .package_manager
+        .unwrap_or_else(pm::default_for_new_project);
+    let mut conf = config::create_conf(opts)?;
+    conf.template = Some(TemplateInfo {
+        name: template.manifest.name.clone(),
+        version: template.manifest.version.clone(),
+        source: template_spec,
+        values,
+    });
+    conf.package_manager = Some(package_manager);
+
+    let target = PathBuf::from(&conf.title);
+    check_target(&target, force)?;
+
+    // the package manager reacts to ctrl+c on its own, the flag covers the steps in between
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let handler_flag = cancelled.clone();
+    ctrlc::set_handler(move || {
+        println!("\nSIGINT received. Rolling back...");
+        handler_flag.store(true, Ordering::SeqCst);
+    })?;
+
+    // build next to the target so the final move is a rename on the same filesystem.
+    // the staging directory is deleted when it goes out of scope, on success or failure
+    let staging = tempfile::Builder::new().prefix(".oseda-init-").tempdir_in(
+        target
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new(".")),
+    )?;
+    // stage under the real title, tools that look at the directory name see the right one
+    let project_dir = staging.path().join(&conf.title);
+    fs::create_dir_all(&project_dir)?;
+
+    if let Err(err) = scaffold(&project_dir, &conf, &template, install_mode, &cancelled) {
+        println!("Could not initialize {}, nothing was created", conf.title);
+        return Err(err);
+    }
 
-    let output = Command::new("npm")
-        .args(["init", "-y", "--prefix", &conf.title])
-        .current_dir(&conf.title)
-        .output()?;
+    move_into_place(&project_dir, &target, staging.path())?;
 
-    // swapped to explicit check so it doesn't hang after
-    if !output.status.success() {
-        eprintln!(
-            "npm init failed: {}",
-            String::from_utf8_lossy(&output.stderr)
+    if install_mode == InstallMode::Skip {
+        println!(
+            "Dependencies were not installed, run `{} install` in {} when you are online",
+            package_manager, conf.title
         );
-        return Err("npm init failed".into());
     }
 
-    let npm_commands = vec![
-        format!("install --save-dev vite@5.4.21 http-server@14.1.1"),
-        format!("install reveal.js@5.2.1 serve@14.2.6 highlight.js@11.12.0"),
-        format!("install patch-package@8.0.1"),
-    ];
-
-    for c in npm_commands {
-        let args: Vec<&str> = c.split(' ').collect();
-        let output = Command::new("npm")
-            .args(&args)
-            .current_dir(&conf.title)
-            .output()?;
-
-        if !output.status.success() {
-            eprintln!(
-                "npm {} failed: {}",
-                c,
-                String::from_utf8_lossy(&output.stderr)
-            );
-            return Err(format!("npm {} failed", c).into());
-        }
-        println!("Bootstrapped npm {}", c);
+    Ok(())
+}
+
+/// Runs every init step inside the staging directory
+///
+/// # Arguments
+/// * `project_dir` - the staged project
+/// * `conf` - config of the new project
+/// * `template` - the template to scaffold from
+/// * `install_mode` - whether to install the dependencies, and from where
+/// * `cancelled` - set once the user hits ctrl+c
+fn scaffold(
+    project_dir: &Path,
+    conf: &OsedaConfig,
+    template: &Template,
+    install_mode: InstallMode,
+    cancelled: &AtomicBool,
+) -> Result<(), Box<dyn Error>> {
+    let package_manager = conf.package_manager.unwrap_or_default();
+    let manifest = PackageManifest::for_template(&template.manifest);
+    manifest.write_package_json(project_dir, &conf.title)?;
+
+    if install_mode != InstallMode::Skip {
+        install_dependencies(
+            project_dir,
+            package_manager,
+            install_mode == InstallMode::Offline,
+            cancelled,
+        )?;
     }
 
     println!("Saving config file...");
 
-    config::write_config(&conf.title, &conf)?;
+    config::write_config(project_dir, conf)?;
+
+    let vars = template::template_vars(conf);
+    template.write_to(project_dir, &vars)?;
+
+    // what `oseda upgrade` merges against later
+    let owned: TemplateFiles = template
+        .render_files(&vars)?
+        .into_iter()
+        .filter(|(path, _)| template.manifest.upgrade.contains(path))
+        .collect();
+    upgrade::record_base(project_dir, &owned, &manifest)?;
+    theme::sync_theme(project_dir, conf)?;
+    chapters::sync_slides(project_dir, conf)?;
+    reveal::sync_reveal_config(project_dir, conf)?;
+    check_cancelled(cancelled)?;
 
-    // 99% sure we'll only ever have to maintain these two template schemas
-    match template {
-        Template::Markdown => {
-            // fs::write(format!("{}/package.json", &conf.title), MD_PACKAGE_JSON)?;
-            fs::write(format!("{}/vite.config.js", &conf.title), MD_VITE_CONFIG_JS)?;
-            fs::write(format!("{}/index.html", &conf.title), MD_INDEX_HTML)?;
-            fs::write(format!("{}/.gitignore", &conf.title), MD_GITIGNORE)?;
+    Ok(())
+}
 
-            std::fs::create_dir_all(format!("{}/src", &conf.title))?;
-            fs::write(format!("{}/src/main.js", &conf.title), MD_MAIN_JS)?;
+/// Installs everything listed in the project's package.json, through oseda's package cache
+///
+/// # Arguments
+/// * `project_dir` - project with a package.json
+/// * `package_manager` - what to install with
+/// * `offline` - only install from the cache
+/// * `cancelled` - set once the user hits ctrl+c
+///
+/// # Returns
+/// * `Ok(())` if everything was installed
+/// * `Err` if the install failed, e.g. a package missing from the cache when offline
+pub fn install_dependencies(
+    project_dir: &Path,
+    package_manager: PackageManager,
+    offline: bool,
+    cancelled: &AtomicBool,
+) -> Result<(), Box<dyn Error>> {
+    let cache_dir = pm::package_cache_dir(package_manager)
+        .ok_or("Could not find a cache directory, set OSEDA_CACHE_DIR")?;
+    fs::create_dir_all(&cache_dir)?;
+
+    let args: Vec<String> = package_manager
+        .install_args()
+        .into_iter()
+        .chain(package_manager.cache_args(&cache_dir, offline)?)
+        .collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    println!("Installing dependencies with {package_manager}...");
+    if let Err(err) = install(package_manager, &args, project_dir, cancelled) {
+        if offline {
+            return Err(format!(
+                "{err}\nHint: some packages are not in the cache yet, run `oseda cache warm` while online"
+            )
+            .into());
+        }
+        return Err(err);
+    }
 
-            std::fs::create_dir_all(format!("{}/slides", &conf.title))?;
-            fs::write(format!("{}/slides/slides.md", &conf.title), MD_SLIDES)?;
+    Ok(())
+}
 
-            std::fs::create_dir_all(format!("{}/css", &conf.title))?;
-            fs::write(format!("{}/css/custom.css", &conf.title), MD_CUSTOM_CSS)?;
+/// Runs the package manager in the project, killing it if init gets cancelled
+///
+/// # Returns
+/// * `Ok(())` if the package manager succeeded
+/// * `Err` if it failed, could not be started, or init was cancelled
+fn install(
+    package_manager: PackageManager,
+    args: &[&str],
+    project_dir: &Path,
+    cancelled: &AtomicBool,
+) -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(package_manager.program())
+        .args(args)
+        .current_dir(project_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // drain stderr on its own thread, the package manager blocks if the pipe fills up
+    let stderr = child.stderr.take();
+    let stderr = thread::spawn(move || {
+        let mut buf = String::new();
+        if let Some(mut stderr) = stderr {
+            let _ = stderr.read_to_string(&mut buf);
+        }
+        buf
+    });
 
-            std::fs::create_dir_all(format!("{}/public", &conf.title))?;
-            fs::write(format!("{}/public/ferris.png", &conf.title), MD_FERRIS)?;
+    // swapped to explicit check so it doesn't hang after
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
         }
-        Template::HTML => {
-            // fs::write(format!("{}/package.json", &conf.title), HTML_PACKAGE_JSON)?;
-            fs::write(
-                format!("{}/vite.config.js", &conf.title),
-                HTML_VITE_CONFIG_JS,
-            )?;
-            fs::write(format!("{}/index.html", &conf.title), HTML_INDEX_HTML)?;
-            fs::write(format!("{}/.gitignore", &conf.title), HTML_GITIGNORE)?;
-
-            std::fs::create_dir_all(format!("{}/src", &conf.title))?;
-            fs::write(format!("{}/src/main.js", &conf.title), HTML_MAIN_JS)?;
-
-            std::fs::create_dir_all(format!("{}/slides", &conf.title))?;
-            fs::write(format!("{}/slides/slides.html", &conf.title), HTML_SLIDES)?;
-
-            std::fs::create_dir_all(format!("{}/css", &conf.title))?;
-            fs::write(format!("{}/css/custom.css", &conf.title), HTML_CUSTOM_CSS)?;
-
-            std::fs::create_dir_all(format!("{}/public", &conf.title))?;
-            fs::write(format!("{}/public/ferris.png", &conf.title), HTML_FERRIS)?
+
+        if cancelled.load(Ordering::SeqCst) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("Init cancelled".into());
         }
+
+        thread::sleep(Duration::from_millis(100));
+    };
+
+    if !status.success() {
+        eprintln!(
+            "{package_manager} {} failed: {}",
+            args.join(" "),
+            stderr.join().unwrap_or_default()
+        );
+        return Err(format!("{package_manager} {} failed", args.join(" ")).into());
+    }
+
+    Ok(())
+}
+
+fn check_cancelled(cancelled: &AtomicBool) -> Result<(), Box<dyn Error>> {
+    if cancelled.load(Ordering::SeqCst) {
+        return Err("Init cancelled".into());
+    }
+
+    Ok(())
+}
+
+/// Makes sure init won't clobber an existing project
+///
+/// # Returns
+/// * `Ok(())` if the target does not exist, is empty, or `force` is set
+/// * `Err` if the target is a file, or a non-empty directory without `force`
+fn check_target(target: &Path, force: bool) -> Result<(), Box<dyn Error>> {
+    if !target.exists() {
+        return Ok(());
+    }
+
+    if !target.is_dir() {
+        return Err(format!("{} already exists and is not a directory", target.display()).into());
+    }
+
+    if fs::read_dir(target)?.next().is_some() && !force {
+        return Err(format!(
+            "{} already exists and is not empty, pass --force to replace it",
+            target.display()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Moves the staged project to its final place, replacing whatever is there
+///
+/// The old directory is moved into the staging directory first, so it is only
+/// deleted once the new project is in place
+fn move_into_place(
+    project_dir: &Path,
+    target: &Path,
+    staging: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let previous = staging.join(".previous");
+
+    let replaced = target.exists();
+    if replaced {
+        fs::rename(target, &previous)?;
+    }
+
+    if let Err(err) = fs::rename(project_dir, target) {
+        if replaced {
+            fs::rename(&previous, target)?;
+        }
+        return Err(format!(
+            "Could not move the project into {}: {err}",
+            target.display()
+        )
+        .into());
     }
 
     Ok(())
 }
 
 fn prompt_template() -> Result<Template, Box<dyn Error>> {
-    let template_opts: Vec<Template> = Template::iter().collect();
+    let template_opts: Vec<String> = template::list()?
+        .into_iter()
+        .map(|t| t.manifest.name)
+        .collect();
 
     let chosen_template = inquire::Select::new("Select a template:", template_opts).prompt()?;
 
-    Ok(chosen_template)
+    template::resolve(&chosen_template)
+}
+
+/// Gets an answer for every prompt in the template manifest
+///
+/// # Arguments
+/// * `manifest` - the template being scaffolded
+/// * `vars` - `KEY=VALUE` answers passed with `--var`, these are not asked again
+///
+/// # Returns
+/// * `Ok(BTreeMap)` with an answer for each prompt
+/// * `Err` if a `--var` is malformed or prompting fails
+fn prompt_values(
+    manifest: &TemplateManifest,
+    vars: &[String],
+) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let mut values = vars
+        .iter()
+        .map(|var| {
+            var.split_once('=')
+                .map(|(k, v)| (k.trim().to_owned(), v.to_owned()))
+                .ok_or_else(|| format!("Invalid --var {var}, expected KEY=VALUE"))
+        })
+        .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+    for prompt in &manifest.prompts {
+        if values.contains_key(&prompt.name) {
+            continue;
+        }
+
+        let mut text = inquire::Text::new(&prompt.message);
+        if let Some(default) = &prompt.default {
+            text = text.with_default(default);
+        }
+        values.insert(prompt.name.clone(), text.prompt()?);
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("IntroToRust");
+
+        assert!(check_target(&target, false).is_ok());
+
+        fs::create_dir_all(&target).unwrap();
+        assert!(check_target(&target, false).is_ok());
+
+        fs::write(target.join("slides.md"), "# Welcome").unwrap();
+        assert!(check_target(&target, false).is_err());
+        assert!(check_target(&target, true).is_ok());
+
+        let file = dir.path().join("file");
+        fs::write(&file, "").unwrap();
+        assert!(check_target(&file, true).is_err());
+    }
+
+    #[test]
+    fn test_scaffold_without_install() {
+        let dir = tempfile::tempdir().unwrap();
+        // the built-in one, whatever is in the user template directory
+        let template = template::builtin_templates()
+            .into_iter()
+            .find(|template| template.manifest.name == "markdown")
+            .unwrap();
+        let conf: OsedaConfig = serde_json::from_str(
+            r##"{
+                "title": "IntroToRust",
+                "author": "JaneDoe",
+                "tags": ["ComputerScience"],
+                "last_updated": "2024-07-10T12:34:56Z",
+                "color": "#FF0000",
+                "description": "Learn rust"
+            }"##,
+        )
+        .unwrap();
+
+        scaffold(
+            dir.path(),
+            &conf,
+            &template,
+            InstallMode::Skip,
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+
+        let package_json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("package.json")).unwrap())
+                .unwrap();
+        let manifest = PackageManifest::base();
+        for (name, version) in &manifest.dependencies {
+            assert_eq!(package_json["dependencies"][name], version.as_str());
+        }
+        for (name, version) in &manifest.dev_dependencies {
+            assert_eq!(package_json["devDependencies"][name], version.as_str());
+        }
+        assert_eq!(package_json["scripts"]["build"], "vite build");
+
+        assert!(!dir.path().join("node_modules").exists());
+        assert!(dir.path().join("oseda-config.json").exists());
+        assert!(dir.path().join(theme::THEME_CSS_PATH).exists());
+        assert!(dir
+            .path()
+            .join(upgrade::TEMPLATE_BASE_DIR)
+            .join("vite.config.js")
+            .exists());
+        assert!(dir.path().join(upgrade::PACKAGE_BASE_FILE).exists());
+        assert!(
+            fs::read_to_string(dir.path().join(chapters::SLIDES_JS_PATH))
+                .unwrap()
+                .contains("../slides/01-introduction.md?raw")
+        );
+        assert!(dir.path().join(reveal::REVEAL_CONFIG_JS_PATH).exists());
+    }
+
+    #[test]
+    fn test_move_into_place_replaces_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("IntroToRust");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("old.md"), "old").unwrap();
+
+        let staging = tempfile::tempdir_in(dir.path()).unwrap();
+        let project_dir = staging.path().join("IntroToRust");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(project_dir.join("oseda-config.json"), "{}").unwrap();
+
+        move_into_place(&project_dir, &target, staging.path()).unwrap();
+        assert!(target.join("oseda-config.json").exists());
+        assert!(!target.join("old.md").exists());
+
+        // dropping the staging directory removes the replaced project with it
+        drop(staging);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
 }
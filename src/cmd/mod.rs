@@ -1,6 +1,15 @@
+pub mod adopt;
+pub mod cache;
 pub mod check;
 pub mod deploy;
 pub mod export;
 pub mod fork;
+pub mod hooks;
 pub mod init;
+pub mod plugin;
 pub mod run;
+pub mod status;
+pub mod template;
+pub mod theme;
+pub mod undeploy;
+pub mod upgrade;
@@ -0,0 +1,275 @@
+use std::{error::Error, fs, path::Path, sync::atomic::AtomicBool};
+
+use clap::{Args, Subcommand};
+
+use crate::{
+    chapters,
+    cmd::init,
+    config::{self, OsedaConfig},
+    package::PackageManifest,
+    pm,
+    reveal::{self, RevealPlugin},
+};
+
+/// Options for the `oseda plugin` command
+#[derive(Args, Debug)]
+pub struct PluginOptions {
+    #[command(subcommand)]
+    pub action: PluginAction,
+}
+
+/// What to do with the reveal.js plugins of the project
+#[derive(Subcommand, Debug)]
+pub enum PluginAction {
+    /// Enable a plugin and install the packages it needs
+    Add(PluginChangeOptions),
+    /// Disable a plugin and uninstall the packages only it needed
+    Remove(PluginChangeOptions),
+}
+
+/// Options for `oseda plugin add` and `oseda plugin remove`
+#[derive(Args, Debug)]
+pub struct PluginChangeOptions {
+    /// The plugin -> e.g. math, mathjax, diagrams, search or zoom
+    pub name: RevealPlugin,
+
+    /// Update package.json without installing or uninstalling packages
+    #[arg(long)]
+    pub no_install: bool,
+}
+
+/// Adds or removes a reveal.js plugin of the Oseda project in the working directory
+///
+/// Plugins are recorded under `reveal.plugins` in oseda-config.json and wired
+/// into src/reveal.config.js
+///
+/// # Arguments
+/// * `opts` - options parsed from CLI flags
+///
+/// # Returns
+/// * `Ok(())` if the plugin was added or removed
+/// * `Err` if the config can't be read, the plugin clashes with another, or installing failed
+pub fn plugin(opts: PluginOptions) -> Result<(), Box<dyn Error>> {
+    let project_dir = Path::new(".");
+    let mut conf = config::read_config_file("oseda-config.json")?;
+
+    let (change, enabled) = match opts.action {
+        PluginAction::Add(change) => (change, true),
+        PluginAction::Remove(change) => (change, false),
+    };
+
+    let has_markdown = chapters::has_markdown_slides(project_dir);
+    if !set_plugin(&mut conf, change.name, enabled, has_markdown)? {
+        println!(
+            "Plugin {} is already {}",
+            change.name,
+            if enabled { "enabled" } else { "disabled" }
+        );
+        return Ok(());
+    }
+
+    let packages_changed = update_packages(project_dir, &conf)?;
+
+    config::write_config(project_dir, &conf)?;
+    reveal::sync_reveal_config(project_dir, &conf)?;
+
+    if packages_changed && !change.no_install {
+        let package_manager = pm::detect(project_dir, Some(&conf));
+        init::install_dependencies(project_dir, package_manager, false, &AtomicBool::new(false))?;
+    }
+
+    println!(
+        "{} plugin {}",
+        if enabled { "Added" } else { "Removed" },
+        change.name
+    );
+    if conf.adopted {
+        println!(
+            "Adopted decks initialize reveal.js themselves, {} in your main.js too",
+            if enabled {
+                "register it"
+            } else {
+                "unregister it"
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Enables or disables a plugin in the config
+///
+/// # Arguments
+/// * `conf` - the project config to change
+/// * `plugin` - the plugin to enable or disable
+/// * `enabled` - true to enable the plugin, false to disable it
+/// * `has_markdown` - whether the project has markdown chapters, which need the markdown plugin
+///
+/// # Returns
+/// * `Ok(true)` if the plugins changed
+/// * `Ok(false)` if the plugin already was enabled or disabled
+/// * `Err` if the plugins would no longer pass `oseda check`, e.g. math along with mathjax
+fn set_plugin(
+    conf: &mut OsedaConfig,
+    plugin: RevealPlugin,
+    enabled: bool,
+    has_markdown: bool,
+) -> Result<bool, Box<dyn Error>> {
+    let reveal = conf.reveal.get_or_insert_with(Default::default);
+    let mut plugins = reveal.plugins();
+
+    if plugins.contains(&plugin) == enabled {
+        return Ok(false);
+    }
+
+    if enabled {
+        plugins.push(plugin);
+    } else {
+        plugins.retain(|p| *p != plugin);
+    }
+
+    let previous = reveal.plugins.replace(plugins);
+    if let Err(err) = reveal.validate(has_markdown) {
+        reveal.plugins = previous;
+        return Err(err.into());
+    }
+
+    Ok(true)
+}
+
+/// Makes package.json list exactly the plugin packages the enabled plugins need
+///
+/// Packages are only removed at the version oseda pins, one the author added
+/// or pinned themselves is theirs to keep
+///
+/// # Returns
+/// * `Ok(true)` if package.json changed
+/// * `Ok(false)` if it already was up to date
+fn update_packages(project_dir: &Path, conf: &OsedaConfig) -> Result<bool, Box<dyn Error>> {
+    let pinned = PackageManifest::base().plugins;
+    let needed: Vec<&str> = conf
+        .reveal
+        .clone()
+        .unwrap_or_default()
+        .plugins()
+        .iter()
+        .flat_map(|plugin| plugin.packages())
+        .copied()
+        .collect();
+
+    let path = project_dir.join("package.json");
+    let mut package_json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+    let dependencies = package_json
+        .as_object_mut()
+        .ok_or("package.json is not a JSON object")?
+        .entry("dependencies")
+        .or_insert_with(|| serde_json::json!({}))
+        .as_object_mut()
+        .ok_or("dependencies in package.json is not a JSON object")?;
+
+    let mut changed = false;
+    for (name, version) in &pinned {
+        let listed = dependencies.get(name);
+
+        // versions the author pinned themselves are kept
+        if needed.contains(&name.as_str()) && listed.is_none() {
+            dependencies.insert(name.clone(), version.clone().into());
+            changed = true;
+        } else if !needed.contains(&name.as_str())
+            && listed.and_then(|listed| listed.as_str()) == Some(version.as_str())
+        {
+            dependencies.remove(name);
+            changed = true;
+        }
+    }
+
+    if changed {
+        fs::write(&path, serde_json::to_string_pretty(&package_json)? + "\n")?;
+    }
+
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample_config() -> OsedaConfig {
+        serde_json::from_str(
+            r##"{
+                "title": "IntroToRust",
+                "author": "JaneDoe",
+                "tags": ["Mathematics"],
+                "last_updated": "2024-07-10T12:34:56Z",
+                "color": "#FF0000",
+                "description": "Learn rust"
+            }"##,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_add_and_remove_plugin() {
+        let dir = tempfile::tempdir().unwrap();
+        PackageManifest::base()
+            .write_package_json(dir.path(), "IntroToRust")
+            .unwrap();
+
+        let mut conf = sample_config();
+
+        assert!(set_plugin(&mut conf, RevealPlugin::Math, true, false).unwrap());
+        assert!(!set_plugin(&mut conf, RevealPlugin::Math, true, false).unwrap());
+        assert_eq!(
+            conf.reveal.as_ref().unwrap().plugins(),
+            vec![
+                RevealPlugin::Markdown,
+                RevealPlugin::Highlight,
+                RevealPlugin::Notes,
+                RevealPlugin::Math
+            ]
+        );
+
+        // both render equations
+        assert!(set_plugin(&mut conf, RevealPlugin::Mathjax, true, false).is_err());
+        assert!(!conf
+            .reveal
+            .as_ref()
+            .unwrap()
+            .plugins()
+            .contains(&RevealPlugin::Mathjax));
+
+        assert!(update_packages(dir.path(), &conf).unwrap());
+        assert!(!update_packages(dir.path(), &conf).unwrap());
+        let package_json = fs::read_to_string(dir.path().join("package.json")).unwrap();
+        assert!(package_json.contains("\"katex\""));
+        assert_eq!(
+            reveal::missing_packages(dir.path(), conf.reveal.as_ref().unwrap()),
+            vec!["katex"]
+        );
+
+        assert!(set_plugin(&mut conf, RevealPlugin::Math, false, false).unwrap());
+        assert!(update_packages(dir.path(), &conf).unwrap());
+        let package_json = fs::read_to_string(dir.path().join("package.json")).unwrap();
+        assert!(!package_json.contains("\"katex\""));
+        assert!(package_json.contains("\"reveal.js\""));
+
+        // markdown chapters can't go without the markdown plugin
+        assert!(set_plugin(&mut conf, RevealPlugin::Markdown, false, true).is_err());
+        assert!(set_plugin(&mut conf, RevealPlugin::Markdown, false, false).unwrap());
+    }
+
+    #[test]
+    fn test_update_packages_keeps_author_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"dependencies": {"reveal.js": "^5.1.0", "mermaid": "^10.9.0"}}"#,
+        )
+        .unwrap();
+
+        // mermaid is the author's, not oseda's pinned version
+        let conf = sample_config();
+        assert!(!update_packages(dir.path(), &conf).unwrap());
+        let package_json = fs::read_to_string(dir.path().join("package.json")).unwrap();
+        assert!(package_json.contains("\"mermaid\""));
+    }
+}
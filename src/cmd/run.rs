@@ -1,4 +1,5 @@
 use std::{
+    path::Path,
     process::Command,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -7,6 +8,8 @@ use std::{
     time::Duration,
 };
 
+use crate::{chapters, config, pm, reveal, theme};
+
 /// More in depth errors that could cause a project not to run
 #[derive(Debug)]
 pub enum OsedaRunError {
@@ -27,7 +30,7 @@ impl std::fmt::Display for OsedaRunError {
 /// Runs an Oseda project in the working directory
 ///
 /// This will:
-/// - Run `npx vite build`
+/// - Run `vite build` through the project's package manager -> e.g. `npx vite build`
 /// - Start a static file server (`serve dist`)
 /// - Gracefully listen for Ctrl+C to shut down the server
 ///     - This gracefull-ness here is important, this runs on a separate thread, do not attempt to orphan this process
@@ -40,29 +43,64 @@ pub fn run() -> Result<(), OsedaRunError> {
 }
 
 pub fn run_with_shutdown(shutdown_flag: Arc<AtomicBool>) -> Result<(), OsedaRunError> {
+    let conf = config::read_config_file("oseda-config.json").ok();
+
+    // keep css/theme.css and src/theme.js in sync with the theme and color in the config
+    if let Some(conf) = &conf {
+        match theme::sync_theme(Path::new("."), conf) {
+            Ok(true) => println!(
+                "Regenerated {} and {} from color {}",
+                theme::THEME_CSS_PATH,
+                theme::THEME_JS_PATH,
+                conf.color
+            ),
+            Ok(false) => {}
+            Err(e) => return Err(OsedaRunError::BuildError(e.to_string())),
+        }
+
+        // and src/slides.js with the files in slides/
+        match chapters::sync_slides(Path::new("."), conf) {
+            Ok(true) => println!("Regenerated {}", chapters::SLIDES_JS_PATH),
+            Ok(false) => {}
+            Err(e) => return Err(OsedaRunError::BuildError(e.to_string())),
+        }
+
+        // and src/reveal.config.js with the reveal options
+        match reveal::sync_reveal_config(Path::new("."), conf) {
+            Ok(true) => println!("Regenerated {}", reveal::REVEAL_CONFIG_JS_PATH),
+            Ok(false) => {}
+            Err(e) => return Err(OsedaRunError::BuildError(e.to_string())),
+        }
+    }
+
+    let package_manager = pm::detect(Path::new("."), conf.as_ref());
+    let mut build = package_manager.exec("vite", &["build"]);
+    let build_command = describe(&build);
+
     // command run failure and command status are considered different, handled accordingly
-    match Command::new("npx").arg("vite").arg("build").status() {
+    match build.status() {
         Ok(status) => {
             if !status.success() {
-                println!("Error: `npx vite build` exited with a failure.");
-                println!("Please ensure that npx and vite are installed properly.");
-                return Err(OsedaRunError::BuildError(
-                    "could not 'npx vite build'".to_string(),
-                ));
+                println!("Error: `{build_command}` exited with a failure.");
+                println!("Please ensure that {package_manager} and vite are installed properly.");
+                return Err(OsedaRunError::BuildError(format!(
+                    "could not '{build_command}'"
+                )));
             }
         }
         Err(e) => {
-            println!("Error: failed to execute `npx vite build`: {e}");
-            println!("Please ensure that `npx` and `vite` are installed and in your PATH.");
-            return Err(OsedaRunError::BuildError(
-                "could not 'npx vite build'".to_string(),
-            ));
+            println!("Error: failed to execute `{build_command}`: {e}");
+            println!(
+                "Please ensure that `{package_manager}` and `vite` are installed and in your PATH."
+            );
+            return Err(OsedaRunError::BuildError(format!(
+                "could not '{build_command}'"
+            )));
         }
     }
 
-    let mut child = Command::new("npx")
-        .arg("serve")
-        .arg("dist")
+    let mut child = package_manager
+        .exec("serve", &["dist"])
         .spawn()
         .map_err(|e| {
             println!("Error starting `serve dist`: {e}");
@@ -98,3 +136,12 @@ pub fn run_with_shutdown(shutdown_flag: Arc<AtomicBool>) -> Result<(), OsedaRunE
 
     Ok(())
 }
+
+/// Formats a command for messages -> e.g. `pnpm exec vite build`
+fn describe(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
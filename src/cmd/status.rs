@@ -0,0 +1,405 @@
+use std::{env, error::Error, path::Path};
+
+use chrono::{DateTime, Utc};
+use clap::Args;
+
+use crate::{
+    cmd::deploy::{
+        course_branch_name, fetch_upstream, resolve_fork_url, sparse_clone, stage_course,
+    },
+    config::{self, OsedaConfig},
+    git::{GitRunner, SystemGit, NETWORK_TIMEOUT},
+    library::{self, LibraryConfig},
+};
+
+/// Options for the `oseda status` command
+#[derive(Args, Debug)]
+pub struct StatusOptions {
+    /// URL of your fork of the library, defaults to the fork saved by `oseda fork --api`
+    fork_url: Option<String>,
+
+    /// Branch the course was deployed to on your fork, defaults to `course/<title>`
+    #[arg(long)]
+    branch: Option<String>,
+}
+
+/// A single file that differs between two versions of a course
+#[derive(Debug, PartialEq)]
+pub struct FileChange {
+    /// git status letter -> e.g. `A`dded, `M`odified, `D`eleted
+    pub kind: char,
+    /// path relative to the course directory
+    pub path: String,
+}
+
+/// Where a course stands between the local project, the fork and the upstream library
+#[derive(Debug, PartialEq)]
+pub enum CourseState {
+    NotDeployed,
+    UpToDate,
+    LocalChangesPending,
+    NotMergedUpstream,
+    UpstreamDiverged,
+}
+
+impl std::fmt::Display for CourseState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotDeployed => write!(f, "Not deployed"),
+            Self::UpToDate => write!(f, "Up to date"),
+            Self::LocalChangesPending => write!(f, "Local changes pending, run `oseda deploy`"),
+            Self::NotMergedUpstream => {
+                write!(f, "Deployed, but not merged into the library yet")
+            }
+            Self::UpstreamDiverged => write!(
+                f,
+                "The library has changes to this course that are not on your fork"
+            ),
+        }
+    }
+}
+
+/// Everything `oseda status` found out about a course
+#[derive(Debug, Default)]
+pub struct StatusReport {
+    pub deployed: bool,
+    pub deployed_last_updated: Option<DateTime<Utc>>,
+    /// local files that differ from the deployed copy
+    pub local_changes: Vec<FileChange>,
+    /// files on the fork that are not in the upstream library
+    pub unmerged: Vec<FileChange>,
+    /// whether upstream has a version of the course that was never deployed
+    pub upstream_diverged: bool,
+}
+
+impl StatusReport {
+    /// Picks the single state that best describes the course, most actionable first
+    pub fn state(&self) -> CourseState {
+        if !self.deployed {
+            CourseState::NotDeployed
+        } else if !self.local_changes.is_empty() {
+            CourseState::LocalChangesPending
+        } else if self.upstream_diverged && !self.unmerged.is_empty() {
+            // with nothing unmerged, upstream has exactly the deployed course
+            CourseState::UpstreamDiverged
+        } else if !self.unmerged.is_empty() {
+            CourseState::NotMergedUpstream
+        } else {
+            CourseState::UpToDate
+        }
+    }
+}
+
+/// Compares the Oseda project in the working directory against what is deployed
+///
+/// # Arguments
+/// * `opts` - options parsed from CLI flags
+///
+/// # Returns
+/// * `Ok(())` once the status was printed
+/// * `Err` if the config is missing or any git step fails
+pub fn status(opts: StatusOptions) -> Result<(), Box<dyn Error>> {
+    let conf = config::read_config_file("oseda-config.json")?;
+    let library = library::resolve(Some(&conf))?;
+    let fork_url = resolve_fork_url(opts.fork_url, Some(&conf))?;
+    let branch = opts
+        .branch
+        .unwrap_or_else(|| course_branch_name(&conf.title));
+
+    let tmp_dir = tempfile::tempdir()?;
+    let repo_path = tmp_dir.path();
+
+    let title = conf.title.clone();
+    let report = build_report(
+        &SystemGit::new().with_network_timeout(NETWORK_TIMEOUT),
+        repo_path,
+        &library,
+        &fork_url,
+        &branch,
+        conf,
+    )?;
+
+    println!("Course: {}", title);
+    println!("Status: {}", report.state());
+
+    if let Some(last_updated) = report.deployed_last_updated {
+        println!("Deployed last_updated: {last_updated}");
+    }
+
+    print_changes("Local changes not deployed:", &report.local_changes);
+    print_changes(
+        &format!("Changes on {branch} not in the library:"),
+        &report.unmerged,
+    );
+
+    Ok(())
+}
+
+/// Fetches the fork and upstream library and compares both with the local project
+fn build_report(
+    git: &dyn GitRunner,
+    repo_path: &Path,
+    library: &LibraryConfig,
+    fork_url: &str,
+    branch: &str,
+    conf: OsedaConfig,
+) -> Result<StatusReport, Box<dyn Error>> {
+    let course_path = library.course_path(&conf.title);
+    sparse_clone(git, repo_path, library, fork_url, &course_path)?;
+
+    let upstream_url = library.ssh_url(&library.web_url())?;
+    let upstream_ref = fetch_upstream(git, repo_path, &upstream_url, &library.default_branch)?;
+
+    let fork_ref = format!("refs/remotes/origin/{}", branch);
+
+    let fork_has_branch = git
+        .stdout(repo_path, &["rev-parse", "--verify", "--quiet", &fork_ref])
+        .is_ok();
+
+    // a merged course may have had its branch deleted, upstream is then the deployed copy
+    let deployed_ref = if fork_has_branch {
+        fork_ref.as_str()
+    } else {
+        upstream_ref.as_str()
+    };
+
+    let deployed_config = format!("{}:{}/oseda-config.json", deployed_ref, course_path);
+    let Ok(deployed_config) = git.stdout(repo_path, &["show", &deployed_config]) else {
+        return Ok(StatusReport::default());
+    };
+
+    let deployed_last_updated = serde_json::from_str::<OsedaConfig>(&deployed_config)
+        .ok()
+        .map(|conf| conf.last_updated);
+
+    // stage the local project over the deployed copy exactly like deploy would,
+    // so ignore rules and the config stamp match what a deploy would commit
+    git.run(
+        repo_path,
+        &["checkout", "--quiet", "--detach", deployed_ref],
+    )?;
+    stage_course(git, repo_path, &course_path, &env::current_dir()?, conf)?;
+
+    let local_changes = parse_name_status(
+        &git.stdout(
+            repo_path,
+            &["diff", "--cached", "--name-status", "--", &course_path],
+        )?,
+        &course_path,
+    );
+
+    let (unmerged, upstream_diverged) = if fork_has_branch {
+        let unmerged = parse_name_status(
+            &git.stdout(
+                repo_path,
+                &[
+                    "diff",
+                    "--name-status",
+                    &upstream_ref,
+                    &fork_ref,
+                    "--",
+                    &course_path,
+                ],
+            )?,
+            &course_path,
+        );
+
+        let diverged = upstream_diverged(git, repo_path, &fork_ref, &upstream_ref, &course_path)?;
+
+        (unmerged, diverged)
+    } else {
+        (Vec::new(), false)
+    };
+
+    Ok(StatusReport {
+        deployed: true,
+        deployed_last_updated,
+        local_changes,
+        unmerged,
+        upstream_diverged,
+    })
+}
+
+/// Whether upstream has a version of the course the fork branch never had
+///
+/// Compares content rather than commits, a squash or rebase merge of the deploy
+/// gives upstream new commits with a course the fork already has
+///
+/// # Arguments
+/// * `fork_ref` - the deploy branch on the fork
+/// * `upstream_ref` - the default branch of the library
+/// * `course_path` - path of the course -> e.g. `courses/IntroToRust`
+fn upstream_diverged(
+    git: &dyn GitRunner,
+    repo_path: &Path,
+    fork_ref: &str,
+    upstream_ref: &str,
+    course_path: &str,
+) -> Result<bool, Box<dyn Error>> {
+    let tree = |rev: &str| {
+        git.stdout(
+            repo_path,
+            &[
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("{rev}:{course_path}"),
+            ],
+        )
+        .ok()
+        .map(|tree| tree.trim().to_owned())
+    };
+
+    // never merged, there is nothing upstream to diverge from
+    let Some(upstream_tree) = tree(upstream_ref) else {
+        return Ok(false);
+    };
+
+    // every version of the course the fork branch has had
+    let deployed = git.stdout(repo_path, &["rev-list", fork_ref, "--", course_path])?;
+
+    Ok(!deployed
+        .lines()
+        .any(|commit| tree(commit).as_deref() == Some(upstream_tree.as_str())))
+}
+
+/// Parses `git diff --name-status` output into changes relative to the course directory
+fn parse_name_status(output: &str, course_path: &str) -> Vec<FileChange> {
+    let prefix = format!("{}/", course_path);
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let (kind, path) = line.split_once('\t')?;
+            // renames and copies list `old\tnew`, the new path is what matters
+            let path = path.rsplit('\t').next()?;
+
+            Some(FileChange {
+                kind: kind.chars().next()?,
+                path: path.strip_prefix(&prefix).unwrap_or(path).to_owned(),
+            })
+        })
+        .collect()
+}
+
+fn print_changes(header: &str, changes: &[FileChange]) {
+    if changes.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{header}");
+    for change in changes {
+        println!("  {} {}", change.kind, change.path);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_name_status() {
+        let output = "M\tcourses/IntroToRust/slides/slides.md\nA\tcourses/IntroToRust/public/crab.png\nR100\tcourses/IntroToRust/a.md\tcourses/IntroToRust/b.md\n";
+
+        let changes = parse_name_status(output, "courses/IntroToRust");
+
+        assert_eq!(
+            changes,
+            vec![
+                FileChange {
+                    kind: 'M',
+                    path: "slides/slides.md".to_owned()
+                },
+                FileChange {
+                    kind: 'A',
+                    path: "public/crab.png".to_owned()
+                },
+                FileChange {
+                    kind: 'R',
+                    path: "b.md".to_owned()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_upstream_diverged_by_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        let git = SystemGit::new()
+            .with_env("GIT_CONFIG_NOSYSTEM", "1")
+            .with_env("GIT_CONFIG_GLOBAL", "/dev/null")
+            .with_env("GIT_AUTHOR_NAME", "Jane Doe")
+            .with_env("GIT_AUTHOR_EMAIL", "jane@example.edu")
+            .with_env("GIT_COMMITTER_NAME", "Jane Doe")
+            .with_env("GIT_COMMITTER_EMAIL", "jane@example.edu");
+        let course = "courses/IntroToRust";
+        let commit = |slides: &str, message: &str| {
+            std::fs::create_dir_all(repo.join(course)).unwrap();
+            std::fs::write(repo.join(course).join("slides.md"), slides).unwrap();
+            git.run(repo, &["add", "--all"]).unwrap();
+            git.run(repo, &["commit", "--quiet", "-m", message])
+                .unwrap();
+        };
+
+        git.run(repo, &["init", "--quiet", "--initial-branch", "main"])
+            .unwrap();
+        git.run(
+            repo,
+            &["commit", "--quiet", "--allow-empty", "-m", "library"],
+        )
+        .unwrap();
+        git.run(repo, &["checkout", "--quiet", "-b", "course/IntroToRust"])
+            .unwrap();
+        commit("# Welcome", "deploy");
+
+        // not merged yet
+        assert!(!upstream_diverged(&git, repo, "course/IntroToRust", "main", course).unwrap());
+
+        // squash merged, upstream has a commit the fork never saw, with the same course
+        git.run(repo, &["checkout", "--quiet", "main"]).unwrap();
+        commit("# Welcome", "squashed deploy");
+        assert!(!upstream_diverged(&git, repo, "course/IntroToRust", "main", course).unwrap());
+
+        // redeployed on top, upstream still has a version the fork had
+        git.run(repo, &["checkout", "--quiet", "course/IntroToRust"])
+            .unwrap();
+        commit("# Welcome back", "redeploy");
+        assert!(!upstream_diverged(&git, repo, "course/IntroToRust", "main", course).unwrap());
+
+        // a maintainer fixed a typo upstream
+        git.run(repo, &["checkout", "--quiet", "main"]).unwrap();
+        commit("# Welcome!", "fix typo");
+        assert!(upstream_diverged(&git, repo, "course/IntroToRust", "main", course).unwrap());
+    }
+
+    #[test]
+    fn test_report_state_priority() {
+        let mut report = StatusReport::default();
+        assert_eq!(report.state(), CourseState::NotDeployed);
+
+        report.deployed = true;
+        assert_eq!(report.state(), CourseState::UpToDate);
+
+        // upstream has the deployed course, however it got merged
+        report.upstream_diverged = true;
+        assert_eq!(report.state(), CourseState::UpToDate);
+        report.upstream_diverged = false;
+
+        report.unmerged.push(FileChange {
+            kind: 'A',
+            path: "oseda-config.json".to_owned(),
+        });
+        assert_eq!(report.state(), CourseState::NotMergedUpstream);
+
+        report.upstream_diverged = true;
+        assert_eq!(report.state(), CourseState::UpstreamDiverged);
+
+        report.local_changes.push(FileChange {
+            kind: 'M',
+            path: "slides/slides.md".to_owned(),
+        });
+        assert_eq!(report.state(), CourseState::LocalChangesPending);
+    }
+}
@@ -0,0 +1,49 @@
+use std::error::Error;
+
+use clap::{Args, Subcommand};
+
+use crate::template;
+
+/// Options for the `oseda template` command
+#[derive(Args, Debug)]
+pub struct TemplateOptions {
+    #[command(subcommand)]
+    pub action: TemplateAction,
+}
+
+/// What to do with the available templates
+#[derive(Subcommand, Debug)]
+pub enum TemplateAction {
+    /// List the templates `oseda init --template <name>` can use
+    List,
+}
+
+/// Manages the templates Oseda projects are created from
+///
+/// # Arguments
+/// * `opts` - options parsed from CLI flags
+///
+/// # Returns
+/// * `Ok(())` once the action is done
+/// * `Err` if a user template can't be loaded
+pub fn template(opts: TemplateOptions) -> Result<(), Box<dyn Error>> {
+    match opts.action {
+        TemplateAction::List => {
+            for template in template::list()? {
+                let manifest = &template.manifest;
+                println!(
+                    "{:<16} {:<10} {}",
+                    manifest.name, manifest.version, manifest.description
+                );
+                println!("{:<16} from {}", "", template.source);
+            }
+
+            if let Some(dir) = template::user_template_dir() {
+                println!();
+                println!("Add your own templates to {}", dir.display());
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use std::{
+    error::Error,
+    fs,
+    io::{self, IsTerminal},
+    path::Path,
+};
+
+use clap::{Args, Subcommand};
+
+use crate::{
+    config,
+    theme::{self, Look, Palette, Rgb, ThemeConfig, Variant},
+};
+
+/// The entrypoint `oseda theme apply` points at src/theme.js
+const MAIN_JS_PATH: &str = "src/main.js";
+
+/// Options for the `oseda theme` command
+#[derive(Args, Debug)]
+pub struct ThemeOptions {
+    #[command(subcommand)]
+    pub action: ThemeAction,
+}
+
+/// What to do with the themes of the project
+#[derive(Subcommand, Debug)]
+pub enum ThemeAction {
+    /// List the themes and highlight styles, with a preview of each
+    List,
+    /// Switch the project to another theme
+    Apply(ThemeApplyOptions),
+}
+
+/// Options for `oseda theme apply`
+#[derive(Args, Debug)]
+pub struct ThemeApplyOptions {
+    /// The theme -> e.g. oseda, lecture, white or moon
+    pub name: String,
+
+    /// dark or light, defaults to dark when the theme has both
+    #[arg(long)]
+    pub variant: Option<Variant>,
+
+    /// Highlight style for code blocks, defaults to the one the theme comes with
+    #[arg(long)]
+    pub highlight: Option<String>,
+}
+
+/// Lists or applies themes
+///
+/// # Arguments
+/// * `opts` - options parsed from CLI flags
+///
+/// # Returns
+/// * `Ok(())` once the action is done
+/// * `Err` if the theme doesn't exist, or the project can't be updated
+pub fn theme(opts: ThemeOptions) -> Result<(), Box<dyn Error>> {
+    match opts.action {
+        ThemeAction::List => {
+            list();
+            Ok(())
+        }
+        ThemeAction::Apply(apply_opts) => apply(apply_opts),
+    }
+}
+
+/// Prints every theme, previewing its colors when the output is a terminal
+fn list() {
+    let conf = config::read_config_file("oseda-config.json").ok();
+    let current = conf
+        .as_ref()
+        .and_then(|conf| theme::resolve(conf.theme.as_ref()).ok());
+    // previews show headings in the course color, or the default one outside of projects
+    let accent = conf
+        .as_ref()
+        .and_then(|conf| Rgb::from_hex(&conf.color))
+        .unwrap_or(Rgb {
+            r: 0x42,
+            g: 0xAF,
+            b: 0xFA,
+        });
+    let preview = io::stdout().is_terminal();
+
+    for theme in theme::THEMES {
+        let variants: Vec<String> = theme.variants().iter().map(|v| v.to_string()).collect();
+        let marker = match &current {
+            Some(current) if current.theme.name == theme.name => "*",
+            _ => " ",
+        };
+
+        println!(
+            "{marker} {:<16} {:<10} {:<12} {}",
+            theme.name,
+            theme.source,
+            variants.join(", "),
+            theme.description
+        );
+
+        if preview {
+            for variant in theme.variants() {
+                let look = theme.look(variant).expect("listed variants exist");
+                println!(
+                    "  {:<16} {} {variant}, highlight {}",
+                    "",
+                    swatch(look, accent),
+                    look.highlight
+                );
+            }
+        }
+    }
+
+    println!();
+    println!("Highlight styles, set with `oseda theme apply <theme> --highlight <style>`:");
+    for style in theme::HIGHLIGHT_STYLES {
+        println!("  {:<20} {}", style.name, style.variant);
+    }
+}
+
+/// A few characters of text and heading on the look's background -> e.g. ` Text Heading `
+fn swatch(look: &Look, accent: Rgb) -> String {
+    let heading = Palette::from_accent(accent, look.background).heading;
+    let bg = look.background;
+
+    format!(
+        "\x1b[48;2;{};{};{}m\x1b[38;2;{};{};{}m Text \x1b[38;2;{};{};{}m\x1b[1mHeading \x1b[0m",
+        bg.r, bg.g, bg.b, look.text.r, look.text.g, look.text.b, heading.r, heading.g, heading.b
+    )
+}
+
+/// Records the theme in the config and regenerates the theme files
+fn apply(opts: ThemeApplyOptions) -> Result<(), Box<dyn Error>> {
+    let project_dir = Path::new(".");
+    let mut conf = config::read_config_file("oseda-config.json")?;
+
+    let theme_conf = ThemeConfig {
+        name: opts.name,
+        variant: opts.variant,
+        highlight: opts.highlight,
+    };
+    let resolved = theme::resolve(Some(&theme_conf))?;
+
+    conf.theme = Some(theme_conf);
+    config::write_config(project_dir, &conf)?;
+    theme::sync_theme(project_dir, &conf)?;
+
+    // projects from before themes import the stylesheets in main.js themselves
+    let main_js_path = project_dir.join(MAIN_JS_PATH);
+    if let Ok(main_js) = fs::read_to_string(&main_js_path) {
+        match rewrite_imports(&main_js) {
+            Some(rewritten) => {
+                fs::write(&main_js_path, rewritten)?;
+                println!("Updated {MAIN_JS_PATH} to import ./theme.js");
+            }
+            None if !main_js.contains("./theme.js") => println!(
+                "Warning: {MAIN_JS_PATH} does not import ./theme.js, add `import \"./theme.js\";` to use the theme"
+            ),
+            None => {}
+        }
+    }
+
+    println!(
+        "Applied theme {} ({}) with highlight style {}",
+        resolved.theme.name, resolved.variant, resolved.highlight.name
+    );
+    Ok(())
+}
+
+/// Swaps the theme and highlight style imports of a main.js for src/theme.js
+///
+/// # Returns
+/// * `Some(String)` with the new main.js
+/// * `None` if it already imports src/theme.js, or imports no theme at all
+fn rewrite_imports(main_js: &str) -> Option<String> {
+    if main_js.contains("\"./theme.js\"") {
+        return None;
+    }
+
+    let is_theme_import = |line: &str| {
+        let line = line.trim();
+        line.starts_with("import")
+            && [
+                "reveal.js/dist/theme/",
+                "reveal.js/plugin/highlight/",
+                "highlight.js/styles/",
+            ]
+            .iter()
+            .any(|path| line.contains(path))
+            && line.contains(".css")
+    };
+
+    let lines: Vec<&str> = main_js.lines().collect();
+    // src/theme.js goes where the reveal.js theme was, after reveal.css
+    let anchor = lines
+        .iter()
+        .position(|line| is_theme_import(line) && line.contains("reveal.js/dist/theme/"))
+        .or_else(|| lines.iter().position(|line| is_theme_import(line)))?;
+
+    let mut rewritten = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i == anchor {
+            rewritten.push_str("import \"./theme.js\";\n");
+        } else if !is_theme_import(line) {
+            rewritten.push_str(line);
+            rewritten.push('\n');
+        }
+    }
+
+    Some(rewritten)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_imports() {
+        let main_js = r#"import Reveal from "reveal.js";
+import "reveal.js/plugin/highlight/monokai.css";
+import "reveal.js/dist/reveal.css";
+import "reveal.js/dist/theme/black.css";
+import "../css/custom.css";
+"#;
+
+        let rewritten = rewrite_imports(main_js).unwrap();
+        assert_eq!(
+            rewritten,
+            r#"import Reveal from "reveal.js";
+import "reveal.js/dist/reveal.css";
+import "./theme.js";
+import "../css/custom.css";
+"#
+        );
+
+        // already done, and nothing to rewrite
+        assert_eq!(rewrite_imports(&rewritten), None);
+        assert_eq!(rewrite_imports("import Reveal from \"reveal.js\";\n"), None);
+    }
+}
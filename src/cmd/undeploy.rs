@@ -0,0 +1,120 @@
+use std::error::Error;
+
+use clap::Args;
+
+use crate::{
+    cmd::deploy::{
+        checkout_course_branch, commit_args, configured_sign, course_branch_name, fetch_upstream,
+        open_pull_request, removal_branch_name, resolve_fork_url, sparse_clone,
+    },
+    config,
+    git::{GitRunner, SystemGit, NETWORK_TIMEOUT},
+    library,
+};
+
+/// Options for the `oseda undeploy` command
+#[derive(Args, Debug)]
+pub struct UndeployOptions {
+    /// URL of your fork of the library, defaults to the fork saved by `oseda fork --api`
+    fork_url: Option<String>,
+
+    /// Name of the course to remove, defaults to the project in the working directory
+    #[arg(long)]
+    course: Option<String>,
+
+    /// Branch to push the removal to on your fork, defaults to `remove/<title>`
+    #[arg(long)]
+    branch: Option<String>,
+
+    /// Open a pull request removing the course from the library
+    #[arg(long)]
+    pr: bool,
+
+    /// Skip the confirmation prompt, useful for scripts
+    #[arg(long, short)]
+    yes: bool,
+}
+
+/// Removes a course from the fork of the library
+///
+/// # Arguments
+/// * `opts` - options parsed from CLI flags
+///
+/// # Returns
+/// * `Ok(())` if the removal was pushed
+/// * `Err` if the course is not deployed, the user cancels, or any git step fails
+pub fn undeploy(opts: UndeployOptions) -> Result<(), Box<dyn Error>> {
+    // the project config is optional here, a course can be removed from anywhere with --course
+    let conf = config::read_config_file("oseda-config.json").ok();
+    let library = library::resolve(conf.as_ref())?;
+
+    let course_name = opts
+        .course
+        .or_else(|| conf.as_ref().map(|c| c.title.clone()))
+        .ok_or("Not in an oseda project, pass the course to remove with --course")?;
+
+    let fork_url = resolve_fork_url(opts.fork_url, conf.as_ref())?;
+
+    let tmp_dir = tempfile::tempdir()?;
+    let repo_path = tmp_dir.path();
+
+    let course_path = library.course_path(&course_name);
+    let git = SystemGit::new().with_network_timeout(NETWORK_TIMEOUT);
+    sparse_clone(&git, repo_path, &library, &fork_url, &course_path)?;
+
+    let branch = opts
+        .branch
+        .unwrap_or_else(|| removal_branch_name(&course_name));
+    // a merged course may have had its branch deleted, the fork's default branch can be
+    // behind the library, so the removal starts from the library itself
+    let upstream_url = library.ssh_url(&library.web_url())?;
+    let upstream_ref = fetch_upstream(&git, repo_path, &upstream_url, &library.default_branch)?;
+    checkout_course_branch(&git, repo_path, &branch, &upstream_ref)?;
+
+    // only on an unmerged deploy branch, there is nothing in the library to remove
+    if !repo_path.join(&course_path).exists() {
+        return Err(format!(
+            "{course_name} is not in {} or on branch {branch} of your fork. \
+             If the pull request from {} is still open, close it instead",
+            library.web_url(),
+            course_branch_name(&course_name)
+        )
+        .into());
+    }
+
+    if !opts.yes {
+        let confirmed = inquire::Confirm::new(&format!(
+            "Remove {course_path} from your fork? This cannot be undone from the CLI"
+        ))
+        .with_default(false)
+        .prompt()?;
+
+        if !confirmed {
+            return Err("Undeploy cancelled".into());
+        }
+    }
+
+    println!("Removing {course_path}...");
+    git.run(repo_path, &["rm", "-r", "--quiet", &course_path])?;
+
+    // same signing requirements as deploy, the library may enforce them
+    let sign = conf.as_ref().and_then(configured_sign);
+    let args = commit_args(&format!("Remove course {course_name}"), &[], sign);
+    git.run(
+        repo_path,
+        &args.iter().map(String::as_str).collect::<Vec<_>>(),
+    )?;
+    git.run(repo_path, &["push", "-u", "origin", &branch])?;
+
+    println!("Removal successfully pushed to branch {branch} on remote.");
+
+    if opts.pr {
+        println!(
+            "Remove your presentation from {} by making a Pull Request at:",
+            library.web_url()
+        );
+        open_pull_request(&library, &fork_url, &branch)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,641 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use clap::Args;
+
+use crate::{
+    cmd::init,
+    config,
+    git::{GitError, GitRunner, SystemGit},
+    package::PackageManifest,
+    pm,
+    template::{self, TemplateFiles},
+};
+
+/// Where oseda keeps project metadata the author commits, but the library never sees
+pub const OSEDA_DIR: &str = ".oseda";
+
+/// Copies of the template files as they were scaffolded, the base of upgrade merges
+pub const TEMPLATE_BASE_DIR: &str = ".oseda/template-base";
+
+/// The package manifest the project was scaffolded or last upgraded with
+pub const PACKAGE_BASE_FILE: &str = ".oseda/package-base.json";
+
+/// Options for the `oseda upgrade` command
+#[derive(Args, Debug)]
+pub struct UpgradeOptions {
+    /// Show what would change without touching any file
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Update package.json without installing the new versions
+    #[arg(long)]
+    pub no_install: bool,
+}
+
+/// What upgrading did to a template file
+#[derive(Debug, PartialEq)]
+pub enum FileUpgrade {
+    /// the project already has the new version
+    Unchanged,
+    /// the author never touched it, replaced with the new version
+    Updated,
+    /// new in this template version
+    Added,
+    /// the author's changes and the template's changes were combined
+    Merged,
+    /// both changed the same lines, the file now has conflict markers
+    Conflicted(usize),
+    /// the author deleted it, so it stays deleted
+    KeptDeleted,
+    /// not text and changed by the author, so it can't be merged
+    KeptBinary,
+}
+
+/// A change to package.json made by upgrading
+#[derive(Debug, PartialEq)]
+pub enum PackageChange {
+    Bumped {
+        section: &'static str,
+        name: String,
+        from: String,
+        to: String,
+    },
+    Added {
+        section: &'static str,
+        name: String,
+        value: String,
+    },
+    Removed {
+        section: &'static str,
+        name: String,
+    },
+    /// the author pinned something else, their value wins
+    Kept {
+        section: &'static str,
+        name: String,
+        yours: String,
+        template: Option<String>,
+    },
+}
+
+/// Upgrades the project in the working directory to the current version of its template
+///
+/// This will:
+/// - Bump the dependencies and scripts in package.json the author left as scaffolded
+/// - Three-way merge the files the template owns -> e.g. `src/main.js` and `vite.config.js`
+/// - Install the new dependencies
+///
+/// The merge base is what init (or the last upgrade) recorded in `.oseda/`
+///
+/// # Arguments
+/// * `opts` - options parsed from CLI flags
+///
+/// # Returns
+/// * `Ok(())` if the project was upgraded without conflicts
+/// * `Err` if the template can't be found, a file can't be merged, or some files have conflicts
+pub fn upgrade(opts: UpgradeOptions) -> Result<(), Box<dyn Error>> {
+    let project_dir = env::current_dir()?;
+    let mut conf = config::read_config_file(project_dir.join("oseda-config.json"))?;
+
+    let Some(mut info) = conf.template.clone() else {
+        return Err("oseda-config.json does not say which template the project was created from, \
+            add e.g. \"template\": { \"name\": \"markdown\", \"version\": \"1.0.0\", \"source\": \"markdown\" }"
+            .into());
+    };
+
+    let template = template::resolve(&info.source)?;
+    println!(
+        "Upgrading from {} {} to {}",
+        info.name, info.version, template.manifest.version
+    );
+
+    let new_files: TemplateFiles = template
+        .render_files(&template::template_vars(&conf))?
+        .into_iter()
+        .filter(|(path, _)| template.manifest.upgrade.contains(path))
+        .collect();
+
+    let git = SystemGit::new();
+    let labels = [
+        "yours".to_owned(),
+        format!("{} {}", info.name, info.version),
+        format!("{} {}", template.manifest.name, template.manifest.version),
+    ];
+
+    let mut conflicts = Vec::new();
+    for (path, new) in &new_files {
+        let base = fs::read(project_dir.join(TEMPLATE_BASE_DIR).join(path)).ok();
+        let result = upgrade_file(
+            &git,
+            &project_dir.join(path),
+            base.as_deref(),
+            new,
+            &labels,
+            opts.dry_run,
+        )?;
+
+        match result {
+            FileUpgrade::Unchanged => {}
+            FileUpgrade::Updated => println!("Updated {path}"),
+            FileUpgrade::Added => println!("Added {path}"),
+            FileUpgrade::Merged => println!("Merged template changes into {path}"),
+            FileUpgrade::Conflicted(count) => {
+                println!("Conflict in {path}: {count} change(s) overlap with yours");
+                conflicts.push(path.clone());
+            }
+            FileUpgrade::KeptDeleted => println!("Kept {path} deleted"),
+            FileUpgrade::KeptBinary => {
+                println!("Kept your {path}, the template changed it too but it can't be merged")
+            }
+        }
+    }
+
+    let new_manifest = PackageManifest::for_template(&template.manifest);
+    let package_changes = upgrade_package(&project_dir, &new_manifest, opts.dry_run)?;
+    for change in &package_changes {
+        print_package_change(change);
+    }
+
+    if opts.dry_run {
+        println!("Dry run, nothing was changed");
+        return Ok(());
+    }
+
+    record_base(&project_dir, &new_files, &new_manifest)?;
+
+    info.name = template.manifest.name.clone();
+    info.version = template.manifest.version.clone();
+    conf.template = Some(info);
+    config::write_config(&project_dir, &conf)?;
+
+    let installs = package_changes
+        .iter()
+        .any(|change| !matches!(change, PackageChange::Kept { .. }));
+    if installs && !opts.no_install {
+        let package_manager = pm::detect(&project_dir, Some(&conf));
+        init::install_dependencies(
+            &project_dir,
+            package_manager,
+            false,
+            &AtomicBool::new(false),
+        )?;
+    }
+
+    if !conflicts.is_empty() {
+        return Err(format!(
+            "{} has conflicts, resolve the <<<<<<< markers before running the course",
+            conflicts.join(", ")
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Saves the template files and package manifest a project now matches, for the next upgrade
+///
+/// # Arguments
+/// * `project_dir` - the project
+/// * `files` - rendered template files `oseda upgrade` owns
+/// * `manifest` - the package manifest package.json was written from
+pub fn record_base(
+    project_dir: &Path,
+    files: &TemplateFiles,
+    manifest: &PackageManifest,
+) -> Result<(), Box<dyn Error>> {
+    let base_dir = project_dir.join(TEMPLATE_BASE_DIR);
+    if base_dir.exists() {
+        fs::remove_dir_all(&base_dir)?;
+    }
+
+    for (path, contents) in files {
+        let dest = base_dir.join(path);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(dest, contents)?;
+    }
+
+    let package_base = project_dir.join(PACKAGE_BASE_FILE);
+    if let Some(parent) = package_base.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(package_base, serde_json::to_string_pretty(manifest)? + "\n")?;
+
+    Ok(())
+}
+
+/// Brings one template file up to date
+///
+/// # Arguments
+/// * `git` - runs `git merge-file`
+/// * `path` - the file in the project
+/// * `base` - the file as scaffolded, `None` for projects without a recorded base
+/// * `new` - the file from the current template
+/// * `labels` - conflict marker labels for the project, base and new versions
+/// * `dry_run` - work out the result without writing anything
+pub fn upgrade_file(
+    git: &dyn GitRunner,
+    path: &Path,
+    base: Option<&[u8]>,
+    new: &[u8],
+    labels: &[String; 3],
+    dry_run: bool,
+) -> Result<FileUpgrade, Box<dyn Error>> {
+    let Ok(current) = fs::read(path) else {
+        if base.is_some() {
+            return Ok(FileUpgrade::KeptDeleted);
+        }
+
+        if !dry_run {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, new)?;
+        }
+        return Ok(FileUpgrade::Added);
+    };
+
+    if current == new || base == Some(new) {
+        return Ok(FileUpgrade::Unchanged);
+    }
+
+    if base == Some(current.as_slice()) {
+        if !dry_run {
+            fs::write(path, new)?;
+        }
+        return Ok(FileUpgrade::Updated);
+    }
+
+    let is_text = |bytes: &[u8]| std::str::from_utf8(bytes).is_ok() && !bytes.contains(&0);
+    if !is_text(&current) || !is_text(new) {
+        return Ok(FileUpgrade::KeptBinary);
+    }
+
+    // no recorded base means nothing is known to be shared, every difference is a conflict
+    let (merged, conflicts) = merge_file(git, &current, base.unwrap_or_default(), new, labels)?;
+    if !dry_run {
+        fs::write(path, merged)?;
+    }
+
+    Ok(match conflicts {
+        0 => FileUpgrade::Merged,
+        count => FileUpgrade::Conflicted(count),
+    })
+}
+
+/// Three-way merges with `git merge-file`
+///
+/// # Returns
+/// * `Ok((Vec<u8>, usize))` with the merged file and how many conflicts it has
+/// * `Err` if git could not merge the files at all
+fn merge_file(
+    git: &dyn GitRunner,
+    current: &[u8],
+    base: &[u8],
+    new: &[u8],
+    labels: &[String; 3],
+) -> Result<(Vec<u8>, usize), Box<dyn Error>> {
+    let dir = tempfile::tempdir()?;
+    let paths: Vec<PathBuf> = ["current", "base", "new"]
+        .iter()
+        .map(|name| dir.path().join(name))
+        .collect();
+    for (path, contents) in paths.iter().zip([current, base, new]) {
+        fs::write(path, contents)?;
+    }
+
+    let result = git.run(
+        dir.path(),
+        &[
+            "merge-file",
+            "-L",
+            &labels[0],
+            "-L",
+            &labels[1],
+            "-L",
+            &labels[2],
+            "current",
+            "base",
+            "new",
+        ],
+    );
+
+    // merge-file exits with the number of conflicts, and a negative code on errors
+    let conflicts = match result {
+        Ok(_) => 0,
+        Err(GitError::Failed {
+            code: Some(code @ 1..=127),
+            ..
+        }) => code as usize,
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok((fs::read(&paths[0])?, conflicts))
+}
+
+/// Applies the new package manifest to the project's package.json
+fn upgrade_package(
+    project_dir: &Path,
+    new: &PackageManifest,
+    dry_run: bool,
+) -> Result<Vec<PackageChange>, Box<dyn Error>> {
+    let path = project_dir.join("package.json");
+    let mut package_json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+
+    let base = match fs::read_to_string(project_dir.join(PACKAGE_BASE_FILE)) {
+        Ok(base) => Some(serde_json::from_str::<PackageManifest>(&base)?),
+        Err(_) => None,
+    };
+
+    let changes = upgrade_package_json(&mut package_json, base.as_ref(), new)?;
+
+    if !dry_run && !changes.is_empty() {
+        fs::write(path, serde_json::to_string_pretty(&package_json)? + "\n")?;
+    }
+
+    Ok(changes)
+}
+
+/// Moves package.json to the new manifest, keeping whatever the author changed
+///
+/// Without a base (projects scaffolded before upgrades existed) the template's
+/// versions win, there is no telling what the author changed
+///
+/// # Arguments
+/// * `package_json` - the project's package.json, edited in place
+/// * `base` - the manifest package.json was last written from
+/// * `new` - the manifest of the current template
+pub fn upgrade_package_json(
+    package_json: &mut serde_json::Value,
+    base: Option<&PackageManifest>,
+    new: &PackageManifest,
+) -> Result<Vec<PackageChange>, Box<dyn Error>> {
+    let package = package_json
+        .as_object_mut()
+        .ok_or("package.json is not a JSON object")?;
+
+    let sections = [
+        (
+            "dependencies",
+            base.map(|b| &b.dependencies),
+            &new.dependencies,
+        ),
+        (
+            "devDependencies",
+            base.map(|b| &b.dev_dependencies),
+            &new.dev_dependencies,
+        ),
+        ("scripts", base.map(|b| &b.scripts), &new.scripts),
+    ];
+
+    let mut changes = Vec::new();
+    for (section, base, new) in sections {
+        let listed = package
+            .entry(section)
+            .or_insert_with(|| serde_json::json!({}))
+            .as_object_mut()
+            .ok_or_else(|| format!("{section} in package.json is not a JSON object"))?;
+
+        let mut names: Vec<&String> = new.keys().collect();
+        names.extend(base.into_iter().flat_map(BTreeMap::keys));
+        names.sort();
+        names.dedup();
+
+        for name in names {
+            let current = listed.get(name).and_then(|v| v.as_str()).map(str::to_owned);
+            let new = new.get(name);
+
+            if current.as_ref() == new {
+                continue;
+            }
+
+            // without a base, everything the template pins is taken as untouched
+            let untouched = base.is_none_or(|base| current.as_ref() == base.get(name));
+
+            if !untouched {
+                if let Some(yours) = current {
+                    changes.push(PackageChange::Kept {
+                        section,
+                        name: name.clone(),
+                        yours,
+                        template: new.cloned(),
+                    });
+                }
+                continue;
+            }
+
+            match (current, new) {
+                (Some(from), Some(to)) => {
+                    listed.insert(name.clone(), to.clone().into());
+                    changes.push(PackageChange::Bumped {
+                        section,
+                        name: name.clone(),
+                        from,
+                        to: to.clone(),
+                    });
+                }
+                (None, Some(value)) => {
+                    listed.insert(name.clone(), value.clone().into());
+                    changes.push(PackageChange::Added {
+                        section,
+                        name: name.clone(),
+                        value: value.clone(),
+                    });
+                }
+                (Some(_), None) => {
+                    listed.remove(name);
+                    changes.push(PackageChange::Removed {
+                        section,
+                        name: name.clone(),
+                    });
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+fn print_package_change(change: &PackageChange) {
+    match change {
+        PackageChange::Bumped {
+            section,
+            name,
+            from,
+            to,
+        } => println!("{section}: {name} {from} -> {to}"),
+        PackageChange::Added {
+            section,
+            name,
+            value,
+        } => println!("{section}: added {name} {value}"),
+        PackageChange::Removed { section, name } => println!("{section}: removed {name}"),
+        PackageChange::Kept {
+            section,
+            name,
+            yours,
+            template: Some(template),
+        } => println!("{section}: kept your {name} {yours}, the template has {template}"),
+        PackageChange::Kept {
+            section,
+            name,
+            yours,
+            template: None,
+        } => println!("{section}: kept your {name} {yours}, the template dropped it"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn labels() -> [String; 3] {
+        [
+            "yours".into(),
+            "markdown 1.0.0".into(),
+            "markdown 1.1.0".into(),
+        ]
+    }
+
+    #[test]
+    fn test_upgrade_file_merges_and_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = SystemGit::new();
+        let path = dir.path().join("vite.config.js");
+
+        let base = "import { defineConfig } from 'vite'\n\nexport default defineConfig({\n  base: './',\n})\n";
+        let new = "import { defineConfig } from 'vite'\n\nexport default defineConfig({\n  base: './',\n  build: { target: 'es2022' },\n})\n";
+
+        // untouched files are replaced
+        fs::write(&path, base).unwrap();
+        let result = upgrade_file(
+            &git,
+            &path,
+            Some(base.as_bytes()),
+            new.as_bytes(),
+            &labels(),
+            false,
+        );
+        assert_eq!(result.unwrap(), FileUpgrade::Updated);
+        assert_eq!(fs::read_to_string(&path).unwrap(), new);
+
+        // changes on other lines are kept
+        let yours = base.replace("import", "// course config\nimport");
+        fs::write(&path, &yours).unwrap();
+        let result = upgrade_file(
+            &git,
+            &path,
+            Some(base.as_bytes()),
+            new.as_bytes(),
+            &labels(),
+            false,
+        );
+        assert_eq!(result.unwrap(), FileUpgrade::Merged);
+        let merged = fs::read_to_string(&path).unwrap();
+        assert!(merged.starts_with("// course config\n"));
+        assert!(merged.contains("es2022"));
+
+        // changes to the same lines conflict
+        let yours = base.replace("base: './',", "base: '/courses/',");
+        fs::write(&path, &yours).unwrap();
+        let result = upgrade_file(
+            &git,
+            &path,
+            Some(base.as_bytes()),
+            new.as_bytes(),
+            &labels(),
+            false,
+        );
+        assert_eq!(result.unwrap(), FileUpgrade::Conflicted(1));
+        let conflicted = fs::read_to_string(&path).unwrap();
+        assert!(conflicted.contains("<<<<<<< yours"));
+        assert!(conflicted.contains(">>>>>>> markdown 1.1.0"));
+
+        // dry runs leave the file alone
+        fs::write(&path, &yours).unwrap();
+        upgrade_file(
+            &git,
+            &path,
+            Some(base.as_bytes()),
+            new.as_bytes(),
+            &labels(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), yours);
+
+        fs::remove_file(&path).unwrap();
+        let result = upgrade_file(
+            &git,
+            &path,
+            Some(base.as_bytes()),
+            new.as_bytes(),
+            &labels(),
+            false,
+        );
+        assert_eq!(result.unwrap(), FileUpgrade::KeptDeleted);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_upgrade_package_json() {
+        let base: PackageManifest = serde_json::from_str(
+            r#"{
+                "dependencies": { "reveal.js": "5.2.1", "highlight.js": "11.12.0", "serve": "14.2.6" },
+                "dev_dependencies": { "vite": "5.4.21" },
+                "scripts": { "build": "vite build" }
+            }"#,
+        )
+        .unwrap();
+        let new: PackageManifest = serde_json::from_str(
+            r#"{
+                "dependencies": { "reveal.js": "5.3.0", "highlight.js": "11.13.0" },
+                "dev_dependencies": { "vite": "6.0.0" },
+                "scripts": { "build": "vite build", "dev": "vite" }
+            }"#,
+        )
+        .unwrap();
+
+        let mut package_json = serde_json::json!({
+            "name": "introtorust",
+            "dependencies": { "reveal.js": "5.2.1", "highlight.js": "11.11.0", "serve": "14.2.6", "katex": "0.16.22" },
+            "devDependencies": { "vite": "5.4.21" },
+            "scripts": { "build": "vite build" }
+        });
+
+        let changes = upgrade_package_json(&mut package_json, Some(&base), &new).unwrap();
+
+        assert_eq!(package_json["dependencies"]["reveal.js"], "5.3.0");
+        // the author pinned their own highlight.js
+        assert_eq!(package_json["dependencies"]["highlight.js"], "11.11.0");
+        assert!(package_json["dependencies"].get("serve").is_none());
+        assert_eq!(package_json["dependencies"]["katex"], "0.16.22");
+        assert_eq!(package_json["devDependencies"]["vite"], "6.0.0");
+        assert_eq!(package_json["scripts"]["dev"], "vite");
+
+        assert!(changes.contains(&PackageChange::Kept {
+            section: "dependencies",
+            name: "highlight.js".into(),
+            yours: "11.11.0".into(),
+            template: Some("11.13.0".into()),
+        }));
+        assert!(changes.contains(&PackageChange::Removed {
+            section: "dependencies",
+            name: "serve".into(),
+        }));
+
+        // without a base, the template's pins win
+        let mut old_project = serde_json::json!({ "dependencies": { "reveal.js": "4.6.0" } });
+        upgrade_package_json(&mut old_project, None, &new).unwrap();
+        assert_eq!(old_project["dependencies"]["reveal.js"], "5.3.0");
+    }
+}
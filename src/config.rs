@@ -1,6 +1,8 @@
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs::File;
 use std::io::BufWriter;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::{ffi::OsString, fs};
 
@@ -8,12 +10,17 @@ use chrono::{DateTime, Utc};
 use inquire::validator::Validation;
 use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumString};
 
 use crate::cmd::check::OsedaCheckError;
 use crate::cmd::init::InitOptions;
 use crate::color::Color;
-use crate::github;
+use crate::library::{self, LibraryConfig};
+use crate::pm::PackageManager;
+use crate::reveal::RevealConfig;
 use crate::tags::Tag;
+use crate::theme::{self, Rgb, ThemeConfig};
+use crate::{chapters, identity};
 
 pub fn read_config_file<P: AsRef<std::path::Path>>(
     path: P,
@@ -35,7 +42,7 @@ pub fn read_config_file<P: AsRef<std::path::Path>>(
 ///
 /// This checks a few things:
 /// - the file exists and parses correctly
-/// - the git `user.name` matches the config author (unless --skip-git is passed)
+/// - the user's GitHub login matches the config author (unless --skip-git is passed)
 /// - the config `title` matches the name of the working directory
 ///
 /// # Arguments
@@ -49,15 +56,64 @@ pub fn read_and_validate_config() -> Result<OsedaConfig, OsedaCheckError> {
         OsedaCheckError::DirectoryNameMismatch("Could not get path of working directory".to_owned())
     })?;
 
+    read_and_validate_config_in(&path)
+}
+
+/// Reads and validates the oseda-config.json in the working directory without touching the network
+///
+/// The author is only checked against an explicit `github.login` setting, since the
+/// token and SSH probes need the git host. Used by `oseda check --static-only`,
+/// which runs before every commit
+pub fn read_and_validate_config_offline() -> Result<OsedaConfig, OsedaCheckError> {
+    let path = std::env::current_dir().map_err(|_| {
+        OsedaCheckError::DirectoryNameMismatch("Could not get path of working directory".to_owned())
+    })?;
+
+    read_and_validate(&path, true)
+}
+
+/// Reads and validates the oseda-config.json of the project in `path`
+///
+/// Same checks as `read_and_validate_config`, for commands working on projects
+/// other than the working directory -> e.g. `oseda deploy --all`
+pub fn read_and_validate_config_in(path: &std::path::Path) -> Result<OsedaConfig, OsedaCheckError> {
+    read_and_validate(path, false)
+}
+
+// `offline` only looks at the configured login, and skips the author check without one
+fn read_and_validate(
+    path: &std::path::Path,
+    offline: bool,
+) -> Result<OsedaConfig, OsedaCheckError> {
     let config_path = path.join("oseda-config.json");
 
     let conf = read_config_file(config_path)?;
 
+    let identity = if offline {
+        identity::configured_login(path)
+    } else {
+        let library = library::resolve(Some(&conf)).unwrap_or_default();
+        identity::github_login(path, &library)
+    };
+
     let in_ci = std::env::var("GITHUB_ACTIONS").is_ok_and(|v| v == "true");
-    let skip_git = in_ci;
+    let skip_git = in_ci || (offline && identity.is_none());
 
-    validate_config(&conf, &path, skip_git, || {
-        github::get_config_from_user_git("user.name")
+    validate_config(&conf, path, skip_git, || {
+        identity.as_ref().map(|identity| identity.login.clone())
+    })
+    .map_err(|err| match err {
+        // say where the login came from, so the user knows what to fix
+        OsedaCheckError::BadGitCredentials(msg) => match &identity {
+            Some(identity) => OsedaCheckError::BadGitCredentials(format!(
+                "{msg}. Your GitHub login is {} (from {})",
+                identity.login, identity.source
+            )),
+            None => {
+                OsedaCheckError::BadGitCredentials(format!("{msg}. {}", identity::login_hint()))
+            }
+        },
+        err => err,
     })?;
 
     Ok(conf)
@@ -69,16 +125,14 @@ pub fn validate_config(
     skip_git: bool,
     // very cool pass in a lambda, swap that lambda out in the tests
     // https://danielbunte.medium.com/a-guide-to-testing-and-mocking-in-rust-a73d022b4075
-    get_git_user: impl Fn() -> Option<String>,
+    get_github_login: impl Fn() -> Option<String>,
 ) -> Result<(), OsedaCheckError> {
     if !skip_git {
-        let gh_name = get_git_user().ok_or_else(|| {
-            OsedaCheckError::BadGitCredentials(
-                "Could not get git user.name from git config".to_owned(),
-            )
+        let login = get_github_login().ok_or_else(|| {
+            OsedaCheckError::BadGitCredentials("Could not determine your GitHub login".to_owned())
         })?;
 
-        if gh_name != conf.author {
+        if !identity::logins_match(&login, &conf.author) {
             return Err(OsedaCheckError::BadGitCredentials(
                 "Config author does not match git credentials".to_owned(),
             ));
@@ -95,12 +149,27 @@ pub fn validate_config(
         ));
     }
 
+    if Rgb::from_hex(&conf.color).is_none() {
+        return Err(OsedaCheckError::BadConfig(format!(
+            "Color {} is not a hex color -> e.g. #FF0000",
+            conf.color
+        )));
+    }
+
+    theme::resolve(conf.theme.as_ref()).map_err(OsedaCheckError::BadConfig)?;
+
     if conf.description.is_empty() {
         return Err(OsedaCheckError::MissingDescription(
             "Description is missing or empty. Please update the oseda-config.json".to_owned(),
         ));
     }
 
+    if let Some(reveal) = &conf.reveal {
+        reveal
+            .validate(chapters::has_markdown_slides(current_dir))
+            .map_err(OsedaCheckError::BadConfig)?;
+    }
+
     Ok(())
 }
 
@@ -110,11 +179,116 @@ pub struct OsedaConfig {
     pub title: String,
     pub author: String,
     pub tags: Vec<Tag>,
-    // effectively mutable. Will get updated on each deployment
+    // effectively mutable. Only moves forward when a deployment changes the content
     pub last_updated: DateTime<Utc>,
+    // hash of the deployable content as of `last_updated`, see `stamp_deployment`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
     pub color: String,
     // description must not be empty for check/deploy
     pub description: String,
+    // only set for courses deploying to a self-hosted library
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library: Option<LibraryConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deploy: Option<DeployConfig>,
+    // added as `Co-authored-by:` trailers on deploy commits
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contributors: Vec<Contributor>,
+    // the template the project was created from, see `oseda template list`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<TemplateInfo>,
+    // npm when unset, unless a lockfile says otherwise, see `pm::detect`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_manager: Option<PackageManager>,
+    // slide files in the order they are presented, relative to slides/. Ordered by filename when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<String>,
+    // reveal.js options and plugins, generated into src/reveal.config.js
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reveal: Option<RevealConfig>,
+    // reveal.js theme and highlight style, see `oseda theme list`. reveal's black theme when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<ThemeConfig>,
+    // set by `oseda adopt`, the deck wires up reveal.js itself so src/slides.js and src/reveal.config.js aren't generated
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub adopted: bool,
+}
+
+/// Which template a project was scaffolded from, and the answers to its prompts
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub version: String,
+    // what was passed to `--template`, a name, path or git url
+    pub source: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, String>,
+}
+
+/// Someone who helped write a course, besides the author
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Contributor {
+    pub name: String,
+    pub email: String,
+}
+
+/// How deploy commits get signed, the key itself comes from the user's git config (`user.signingkey`)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum SignMode {
+    Gpg,
+    Ssh,
+}
+
+/// Per project deploy settings, stored under `deploy` in the oseda-config.json
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct DeployConfig {
+    // fork deploys go to when no url is passed, set by `oseda fork --api`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fork_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sign: Option<SignMode>,
+}
+
+/// Structure for the user wide config, stored at `~/.config/oseda/config.json`
+#[derive(Serialize, Deserialize, Default)]
+pub struct GlobalConfig {
+    #[serde(default)]
+    pub library: Option<LibraryConfig>,
+}
+
+/// Gets the directory holding the user wide oseda config
+///
+/// `OSEDA_CONFIG_DIR` takes priority, otherwise this is `oseda` inside the
+/// platform config directory -> e.g. `~/.config/oseda`
+pub fn global_config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("OSEDA_CONFIG_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+
+    dirs::config_dir().map(|dir| dir.join("oseda"))
+}
+
+/// Reads the user wide oseda config
+///
+/// # Returns
+/// * `Ok(GlobalConfig)` with the parsed config, or the default config if no file exists
+/// * `Err` if the file exists but cannot be read or parsed
+pub fn read_global_config() -> Result<GlobalConfig, Box<dyn Error>> {
+    let Some(path) = global_config_dir().map(|dir| dir.join("config.json")) else {
+        return Ok(GlobalConfig::default());
+    };
+
+    if !path.exists() {
+        return Ok(GlobalConfig::default());
+    }
+
+    let config_str = fs::read_to_string(&path)?;
+    let conf = serde_json::from_str(&config_str)
+        .map_err(|e| format!("Could not parse global config {}: {e}", path.display()))?;
+
+    Ok(conf)
 }
 
 pub fn prompt_for_title() -> Result<String, Box<dyn Error>> {
@@ -160,17 +334,29 @@ pub fn create_conf(options: InitOptions) -> Result<OsedaConfig, Box<dyn Error>>
         None => prompt_for_color()?,
     };
 
-    let user_name = github::get_config_from_user_git("user.name")
-        .ok_or("Could not get github username. Please ensure you are signed into github")?;
+    let cwd = std::env::current_dir()?;
+    let user_name = identity::github_login(&cwd, &library::resolve(None)?)
+        .map(|identity| identity.login)
+        .ok_or_else(|| format!("Could not get github username. {}", identity::login_hint()))?;
 
     Ok(OsedaConfig {
         title: title.trim().to_owned(),
         author: user_name,
         tags,
         last_updated: get_time(),
+        content_hash: None,
         color: color.into_hex(),
         // start them with empty description
         description: String::new(),
+        library: None,
+        deploy: None,
+        contributors: Vec::new(),
+        template: None,
+        package_manager: None,
+        chapters: Vec::new(),
+        reveal: None,
+        theme: None,
+        adopted: false,
     })
 }
 
@@ -208,20 +394,33 @@ fn prompt_for_color() -> Result<Color, Box<dyn Error>> {
     Ok(selected_color)
 }
 
-/// Updates the configs last-updated
-/// Currently this is used on creation only, TODO fix this
+/// Stamps the config of a course about to be deployed
+///
+/// `last_updated` only moves forward when the deployable content hash differs from the
+/// previously deployed copy, so redeploying an unchanged course keeps its original time
 ///
 /// # Arguments
-/// * `conf` - a previously loaded or generated OsedaConfig
+/// * `conf` - the config that will be committed with the course
+/// * `content_hash` - hash of the course content about to be deployed
+/// * `previous` - the config of the currently deployed copy, if there is one
 ///
 /// # Returns
-/// * `Ok(())` if the file is successfully updated
-/// * `Err` if file writing fails
-pub fn update_time(mut conf: OsedaConfig) -> Result<(), Box<dyn Error>> {
-    conf.last_updated = get_time();
+/// * `true` if the content changed and `last_updated` was bumped
+/// * `false` if the content is identical to the previous deployment
+pub fn stamp_deployment(
+    conf: &mut OsedaConfig,
+    content_hash: String,
+    previous: Option<&OsedaConfig>,
+) -> bool {
+    let unchanged = previous.is_some_and(|prev| prev.content_hash.as_ref() == Some(&content_hash));
+
+    conf.last_updated = match previous {
+        Some(prev) if unchanged => prev.last_updated,
+        _ => get_time(),
+    };
+    conf.content_hash = Some(content_hash);
 
-    write_config(".", &conf)?;
-    Ok(())
+    !unchanged
 }
 
 /// Gets the current system time in UTC
@@ -238,11 +437,14 @@ fn get_time() -> DateTime<Utc> {
 /// * `path` - the directory path to write into
 /// * `conf` - the `OsedaConfig` instance to serialize via serde
 ///
-/// # Returns            color: Color::Black
+/// # Returns
 /// * `Ok(())` if the file is written successfully
 /// * `Err` if file creation or serialization fails
-pub fn write_config(path: &str, conf: &OsedaConfig) -> Result<(), Box<dyn Error>> {
-    let file = File::create(format!("{}/oseda-config.json", path))?;
+pub fn write_config<P: AsRef<std::path::Path>>(
+    path: P,
+    conf: &OsedaConfig,
+) -> Result<(), Box<dyn Error>> {
+    let file = File::create(path.as_ref().join("oseda-config.json"))?;
     let writer = BufWriter::new(file);
 
     serde_json::to_writer_pretty(writer, &conf)?;
@@ -287,8 +489,18 @@ mod test {
             author: "JaneDoe".to_string(),
             tags: vec![Tag::ComputerScience],
             last_updated: chrono::Utc::now(),
+            content_hash: None,
             color: Color::Black.into_hex(),
             description: String::from("Test Description"),
+            library: None,
+            deploy: None,
+            contributors: Vec::new(),
+            template: None,
+            package_manager: None,
+            chapters: Vec::new(),
+            reveal: None,
+            theme: None,
+            adopted: false,
         };
 
         let fake_dir = Path::new("/tmp/my-project");
@@ -298,6 +510,39 @@ mod test {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_validate_config_login_case_insensitive() {
+        let conf = OsedaConfig {
+            title: "my-project".to_string(),
+            author: "JaneDoe".to_string(),
+            tags: vec![Tag::ComputerScience],
+            last_updated: chrono::Utc::now(),
+            content_hash: None,
+            color: Color::Black.into_hex(),
+            description: String::from("Test Description"),
+            library: None,
+            deploy: None,
+            contributors: Vec::new(),
+            template: None,
+            package_manager: None,
+            chapters: Vec::new(),
+            reveal: None,
+            theme: None,
+            adopted: false,
+        };
+
+        let fake_dir = Path::new("/tmp/my-project");
+        // github logins are case insensitive, a display name would not be
+        let result = validate_config(&conf, fake_dir, false, || Some("janedoe".to_string()));
+        assert!(result.is_ok());
+
+        let result = validate_config(&conf, fake_dir, false, || Some("Jane Doe".to_string()));
+        assert!(matches!(result, Err(OsedaCheckError::BadGitCredentials(_))));
+
+        let result = validate_config(&conf, fake_dir, false, || None);
+        assert!(matches!(result, Err(OsedaCheckError::BadGitCredentials(_))));
+    }
+
     #[test]
     fn test_validate_config_bad_git_user() {
         let conf = OsedaConfig {
@@ -305,8 +550,18 @@ mod test {
             author: "JaneDoe".to_string(),
             tags: vec![Tag::ComputerScience],
             last_updated: chrono::Utc::now(),
+            content_hash: None,
             color: Color::Black.into_hex(),
             description: String::from("Test Description"),
+            library: None,
+            deploy: None,
+            contributors: Vec::new(),
+            template: None,
+            package_manager: None,
+            chapters: Vec::new(),
+            reveal: None,
+            theme: None,
+            adopted: false,
         };
 
         let fake_dir = Path::new("/tmp/oseda");
@@ -323,8 +578,18 @@ mod test {
             author: "JaneDoe".to_string(),
             tags: vec![Tag::ComputerScience],
             last_updated: chrono::Utc::now(),
+            content_hash: None,
             color: Color::Black.into_hex(),
             description: String::new(),
+            library: None,
+            deploy: None,
+            contributors: Vec::new(),
+            template: None,
+            package_manager: None,
+            chapters: Vec::new(),
+            reveal: None,
+            theme: None,
+            adopted: false,
         };
 
         let fake_dir = Path::new("/tmp/wrong-name");
@@ -343,8 +608,18 @@ mod test {
             author: "JaneDoe".to_string(),
             tags: vec![Tag::ComputerScience],
             last_updated: chrono::Utc::now(),
+            content_hash: None,
             color: Color::Black.into_hex(),
             description: String::from("Test Description"),
+            library: None,
+            deploy: None,
+            contributors: Vec::new(),
+            template: None,
+            package_manager: None,
+            chapters: Vec::new(),
+            reveal: None,
+            theme: None,
+            adopted: false,
         };
 
         let fake_dir = Path::new("/tmp/oseda");
@@ -352,4 +627,70 @@ mod test {
         let result = validate_config(&conf, fake_dir, true, || None);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_stamp_deployment_unchanged_content_keeps_time() {
+        let deployed_at = "2024-07-10T12:34:56Z".parse::<DateTime<Utc>>().unwrap();
+        let previous = OsedaConfig {
+            title: "oseda".to_string(),
+            author: "JaneDoe".to_string(),
+            tags: vec![Tag::ComputerScience],
+            last_updated: deployed_at,
+            content_hash: Some("abc123".to_string()),
+            color: Color::Black.into_hex(),
+            description: String::from("Test Description"),
+            library: None,
+            deploy: None,
+            contributors: Vec::new(),
+            template: None,
+            package_manager: None,
+            chapters: Vec::new(),
+            reveal: None,
+            theme: None,
+            adopted: false,
+        };
+        let mut conf = read_back(&previous);
+        conf.last_updated = chrono::Utc::now();
+
+        let changed = stamp_deployment(&mut conf, "abc123".to_string(), Some(&previous));
+
+        assert!(!changed);
+        assert_eq!(conf.last_updated, deployed_at);
+    }
+
+    #[test]
+    fn test_stamp_deployment_changed_content_bumps_time() {
+        let deployed_at = "2024-07-10T12:34:56Z".parse::<DateTime<Utc>>().unwrap();
+        let previous = OsedaConfig {
+            title: "oseda".to_string(),
+            author: "JaneDoe".to_string(),
+            tags: vec![Tag::ComputerScience],
+            last_updated: deployed_at,
+            content_hash: Some("abc123".to_string()),
+            color: Color::Black.into_hex(),
+            description: String::from("Test Description"),
+            library: None,
+            deploy: None,
+            contributors: Vec::new(),
+            template: None,
+            package_manager: None,
+            chapters: Vec::new(),
+            reveal: None,
+            theme: None,
+            adopted: false,
+        };
+        let mut conf = read_back(&previous);
+
+        let changed = stamp_deployment(&mut conf, "def456".to_string(), Some(&previous));
+
+        assert!(changed);
+        assert!(conf.last_updated > deployed_at);
+        assert_eq!(conf.content_hash.as_deref(), Some("def456"));
+    }
+
+    fn read_back(conf: &OsedaConfig) -> OsedaConfig {
+        let dir = tempdir().unwrap();
+        write_config(dir.path(), conf).unwrap();
+        read_config_file(dir.path().join("oseda-config.json")).unwrap()
+    }
 }
@@ -0,0 +1,43 @@
+use std::{error::Error, fs, path::Path};
+
+/// Writes a generated file, creating its directory, unless it already has `contents`
+///
+/// Keeps the files oseda generates (src/slides.js, src/reveal.config.js, the theme)
+/// from being touched when nothing changed, so vite doesn't reload for nothing
+///
+/// # Arguments
+/// * `path` - where the file goes
+/// * `contents` - what the file should contain
+///
+/// # Returns
+/// * `Ok(true)` if the file was (re)written
+/// * `Ok(false)` if it was already up to date
+/// * `Err` if the file or its directory can't be written
+pub(crate) fn write_if_changed(path: &Path, contents: &str) -> Result<bool, Box<dyn Error>> {
+    if fs::read_to_string(path).is_ok_and(|existing| existing == contents) {
+        return Ok(false);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_write_if_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("src/generated.js");
+
+        assert!(write_if_changed(&path, "export default 1;\n").unwrap());
+        assert!(!write_if_changed(&path, "export default 1;\n").unwrap());
+        assert!(write_if_changed(&path, "export default 2;\n").unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "export default 2;\n");
+    }
+}
@@ -0,0 +1,393 @@
+use std::{
+    io::Read,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// What a successful git command printed
+#[derive(Debug, Default, Clone)]
+pub struct GitOutput {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Common ways a git command fails, classified from its exit status and stderr
+#[derive(Debug)]
+pub enum GitError {
+    NotInstalled,
+    Io(String),
+    Auth {
+        args: String,
+        stderr: String,
+    },
+    AlreadyExists {
+        args: String,
+        stderr: String,
+    },
+    NonFastForward {
+        args: String,
+        stderr: String,
+    },
+    SparseCheckoutUnsupported {
+        args: String,
+        stderr: String,
+    },
+    Timeout {
+        args: String,
+        after: Duration,
+    },
+    Failed {
+        args: String,
+        code: Option<i32>,
+        stderr: String,
+    },
+}
+
+impl GitError {
+    /// Classifies a failed git command from what it wrote to stderr
+    ///
+    /// # Arguments
+    /// * `args` - the arguments git was run with
+    /// * `code` - the exit code, `None` if git was killed by a signal
+    /// * `stderr` - everything git wrote to stderr
+    pub fn classify(args: &[&str], code: Option<i32>, stderr: &str) -> Self {
+        let args = args.join(" ");
+        let lower = stderr.to_lowercase();
+        let stderr = stderr.trim().to_owned();
+
+        let any = |needles: &[&str]| needles.iter().any(|needle| lower.contains(needle));
+
+        if any(&[
+            "permission denied",
+            "authentication failed",
+            "could not read username",
+            "could not read from remote repository",
+            "host key verification failed",
+            "repository not found",
+        ]) {
+            GitError::Auth { args, stderr }
+        } else if any(&["non-fast-forward", "fetch first", "updates were rejected"]) {
+            GitError::NonFastForward { args, stderr }
+        } else if args.starts_with("sparse-checkout")
+            && any(&[
+                "is not a git command",
+                "unknown subcommand",
+                "unknown option",
+            ])
+        {
+            GitError::SparseCheckoutUnsupported { args, stderr }
+        } else if any(&["already exists"]) {
+            GitError::AlreadyExists { args, stderr }
+        } else {
+            GitError::Failed { args, code, stderr }
+        }
+    }
+
+    /// What the user can do about the failure, if there is something specific
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::NotInstalled => Some("Install git and make sure it is in your PATH"),
+            Self::Auth { .. } => Some(
+                "Check that your SSH key is added to your git host (try `ssh -T git@github.com`) and that you can access the repository",
+            ),
+            Self::AlreadyExists { .. } => {
+                Some("Something with this name already exists, remove it or pick another name")
+            }
+            Self::NonFastForward { .. } => Some(
+                "The remote branch has commits you don't have locally. Delete the branch on your fork, or merge it, then deploy again",
+            ),
+            Self::SparseCheckoutUnsupported { .. } => {
+                Some("Sparse checkout needs git 2.25 or newer, please update git")
+            }
+            Self::Timeout { .. } => Some("git took too long, check your network connection"),
+            Self::Io(_) | Self::Failed { .. } => None,
+        }
+    }
+}
+
+impl std::error::Error for GitError {}
+
+impl std::fmt::Display for GitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotInstalled => write!(f, "git is not installed")?,
+            Self::Io(msg) => write!(f, "Could not run git: {}", msg)?,
+            Self::Auth { args, stderr } => {
+                write!(f, "git {} could not authenticate: {}", args, stderr)?
+            }
+            Self::AlreadyExists { args, stderr } => {
+                write!(f, "git {} failed, already exists: {}", args, stderr)?
+            }
+            Self::NonFastForward { args, stderr } => {
+                write!(f, "git {} was rejected: {}", args, stderr)?
+            }
+            Self::SparseCheckoutUnsupported { args, stderr } => write!(
+                f,
+                "git {} failed, sparse checkout is not supported: {}",
+                args, stderr
+            )?,
+            Self::Timeout { args, after } => {
+                write!(f, "git {} timed out after {}s", args, after.as_secs())?
+            }
+            Self::Failed { args, code, stderr } => match code {
+                Some(code) => write!(f, "git {} failed with exit code {}: {}", args, code, stderr)?,
+                None => write!(f, "git {} was killed: {}", args, stderr)?,
+            },
+        }
+
+        if let Some(hint) = self.hint() {
+            write!(f, "\nHint: {}", hint)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Something that can run git commands
+///
+/// Deploy, undeploy and status go through this instead of spawning git themselves,
+/// so they can be pointed at a differently configured git in tests
+pub trait GitRunner {
+    /// Runs git with `args` in `dir`
+    ///
+    /// # Returns
+    /// * `Ok(GitOutput)` with the captured output if git exits successfully
+    /// * `Err(GitError)` describing why it failed otherwise
+    fn run(&self, dir: &Path, args: &[&str]) -> Result<GitOutput, GitError>;
+
+    /// Runs git and returns only its stdout, for commands whose output we need to read
+    fn stdout(&self, dir: &Path, args: &[&str]) -> Result<String, GitError> {
+        self.run(dir, args).map(|output| output.stdout)
+    }
+
+    /// Runs git and only returns whether it succeeded, for probes like `rev-parse --verify`
+    fn succeeds(&self, dir: &Path, args: &[&str]) -> bool {
+        self.run(dir, args).is_ok()
+    }
+}
+
+/// How long clones, fetches and pushes may take before they count as hung
+pub const NETWORK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+// git commands that talk to a remote
+const NETWORK_COMMANDS: [&str; 5] = ["clone", "fetch", "pull", "push", "ls-remote"];
+
+/// Runs the git binary on the PATH
+#[derive(Debug, Default, Clone)]
+pub struct SystemGit {
+    timeout: Option<Duration>,
+    network_timeout: Option<Duration>,
+    env: Vec<(String, String)>,
+}
+
+impl SystemGit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Kills git and fails with `GitError::Timeout` if a command runs longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Like `with_timeout`, but only for commands talking to a remote, see `NETWORK_COMMANDS`
+    ///
+    /// Local commands can take as long as they need, a clone waiting on an unreachable host can't
+    pub fn with_network_timeout(mut self, timeout: Duration) -> Self {
+        self.network_timeout = Some(timeout);
+        self
+    }
+
+    /// The timeout for a command, if it has one
+    fn timeout_for(&self, args: &[&str]) -> Option<Duration> {
+        // skip `-c key=value` and `-C dir` to get to the subcommand
+        let mut rest = args;
+        while let [flag, _, tail @ ..] = rest {
+            if *flag != "-c" && *flag != "-C" {
+                break;
+            }
+            rest = tail;
+        }
+
+        let talks_to_remote = rest
+            .first()
+            .is_some_and(|command| NETWORK_COMMANDS.contains(command));
+
+        match (self.timeout, self.network_timeout) {
+            (Some(timeout), _) => Some(timeout),
+            (None, Some(timeout)) if talks_to_remote => Some(timeout),
+            _ => None,
+        }
+    }
+
+    /// Sets an environment variable for every git command -> e.g. `GIT_AUTHOR_NAME`
+    pub fn with_env(mut self, key: &str, value: &str) -> Self {
+        self.env.push((key.to_owned(), value.to_owned()));
+        self
+    }
+}
+
+impl GitRunner for SystemGit {
+    fn run(&self, dir: &Path, args: &[&str]) -> Result<GitOutput, GitError> {
+        let mut child = Command::new("git")
+            .current_dir(dir)
+            .args(args)
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => GitError::NotInstalled,
+                _ => GitError::Io(e.to_string()),
+            })?;
+
+        // read both pipes on their own threads, git blocks if either one fills up
+        let stdout = read_pipe(child.stdout.take());
+        let stderr = read_pipe(child.stderr.take());
+
+        let started = Instant::now();
+        let timeout = self.timeout_for(args);
+        let status = loop {
+            match child.try_wait().map_err(|e| GitError::Io(e.to_string()))? {
+                Some(status) => break status,
+                None => {
+                    if let Some(timeout) = timeout {
+                        if started.elapsed() > timeout {
+                            let _ = child.kill();
+                            let _ = child.wait();
+                            return Err(GitError::Timeout {
+                                args: args.join(" "),
+                                after: timeout,
+                            });
+                        }
+                    }
+                    thread::sleep(Duration::from_millis(20));
+                }
+            }
+        };
+
+        let output = GitOutput {
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        };
+
+        if !status.success() {
+            return Err(GitError::classify(args, status.code(), &output.stderr));
+        }
+
+        Ok(output)
+    }
+}
+
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).to_string()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_classify_errors() {
+        assert!(matches!(
+            GitError::classify(
+                &["clone", "git@github.com:jane/oseda-lib.git", "."],
+                Some(128),
+                "git@github.com: Permission denied (publickey).\r\nfatal: Could not read from remote repository."
+            ),
+            GitError::Auth { .. }
+        ));
+        assert!(matches!(
+            GitError::classify(
+                &["push", "-u", "origin", "course/IntroToRust"],
+                Some(1),
+                " ! [rejected]        course/IntroToRust -> course/IntroToRust (fetch first)\nerror: failed to push some refs"
+            ),
+            GitError::NonFastForward { .. }
+        ));
+        assert!(matches!(
+            GitError::classify(
+                &["sparse-checkout", "init", "--cone"],
+                Some(1),
+                "git: 'sparse-checkout' is not a git command. See 'git --help'."
+            ),
+            GitError::SparseCheckoutUnsupported { .. }
+        ));
+        assert!(matches!(
+            GitError::classify(
+                &["checkout", "-b", "course/IntroToRust"],
+                Some(128),
+                "fatal: a branch named 'course/IntroToRust' already exists"
+            ),
+            GitError::AlreadyExists { .. }
+        ));
+        assert!(matches!(
+            GitError::classify(&["status"], Some(128), "fatal: not a git repository"),
+            GitError::Failed {
+                code: Some(128),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_system_git_captures_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = SystemGit::new();
+
+        let output = git.run(dir.path(), &["--version"]).unwrap();
+        assert!(output.stdout.starts_with("git version"));
+
+        let err = git.run(dir.path(), &["rev-parse", "HEAD"]).unwrap_err();
+        assert!(matches!(err, GitError::Failed { .. }));
+    }
+
+    #[test]
+    fn test_system_git_kills_slow_network_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = SystemGit::new().with_network_timeout(Duration::from_millis(200));
+
+        // the ext transport runs a command as the remote, this one never answers
+        let started = Instant::now();
+        let err = git
+            .run(
+                dir.path(),
+                &[
+                    "-c",
+                    "protocol.ext.allow=always",
+                    "ls-remote",
+                    "ext::sleep 30",
+                ],
+            )
+            .unwrap_err();
+
+        assert!(matches!(err, GitError::Timeout { .. }));
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // local commands are left alone
+        assert_eq!(git.timeout_for(&["status"]), None);
+        assert_eq!(
+            git.timeout_for(&["clone", "--no-checkout", "url", "."]),
+            Some(Duration::from_millis(200))
+        );
+    }
+
+    #[test]
+    fn test_system_git_env_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = SystemGit::new().with_env("GIT_CONFIG_PARAMETERS", "'oseda.test=yes'");
+
+        let output = git.run(dir.path(), &["config", "oseda.test"]).unwrap();
+        assert_eq!(output.stdout.trim(), "yes");
+    }
+}
@@ -0,0 +1,630 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use strum_macros::Display;
+
+// git refuses to follow more nested includes than this, so do we
+const MAX_INCLUDE_DEPTH: usize = 10;
+
+/// Which git config file a value was read from, in increasing order of precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ConfigScope {
+    System,
+    Xdg,
+    Global,
+    Local,
+}
+
+/// A single `key = value` line from a git config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigEntry {
+    /// normalized key -> e.g. `user.name`, `includeif.gitdir:~/work/.path`
+    pub key: String,
+    pub value: String,
+    pub scope: ConfigScope,
+    /// the file the value is written in, which may be an included file
+    pub origin: PathBuf,
+    pub line: usize,
+}
+
+impl std::fmt::Display for ConfigEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is `{}`, set in {}:{} ({})",
+            self.key,
+            self.value,
+            self.origin.display(),
+            self.line,
+            self.scope
+        )
+    }
+}
+
+/// What `[includeIf]` conditions are evaluated against
+#[derive(Debug, Default, Clone)]
+pub struct IncludeContext {
+    /// the `.git` directory of the current repository
+    pub git_dir: Option<PathBuf>,
+    /// the checked out branch, without `refs/heads/`
+    pub branch: Option<String>,
+}
+
+/// Every git config value visible from a directory, in the order git reads them
+#[derive(Debug, Default)]
+pub struct GitConfig {
+    entries: Vec<ConfigEntry>,
+}
+
+impl GitConfig {
+    /// Loads the system, XDG, global and repository config for `dir`, following includes
+    ///
+    /// Respects `GIT_CONFIG_NOSYSTEM`, `GIT_CONFIG_SYSTEM`, `GIT_CONFIG_GLOBAL` and `XDG_CONFIG_HOME`
+    /// the same way git does. Missing or unreadable files are skipped
+    pub fn load(dir: &Path) -> Self {
+        let git_dir = find_git_dir(dir);
+        let ctx = IncludeContext {
+            branch: git_dir.as_deref().and_then(current_branch),
+            git_dir: git_dir.clone(),
+        };
+
+        let mut files = Vec::new();
+
+        if std::env::var_os("GIT_CONFIG_NOSYSTEM").is_none() {
+            let system = std::env::var_os("GIT_CONFIG_SYSTEM")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("/etc/gitconfig"));
+            files.push((ConfigScope::System, system));
+        }
+
+        match std::env::var_os("GIT_CONFIG_GLOBAL") {
+            // an explicit global file replaces both ~/.gitconfig and the XDG file
+            Some(global) => files.push((ConfigScope::Global, PathBuf::from(global))),
+            None => {
+                let xdg = std::env::var_os("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .or_else(|| dirs::home_dir().map(|home| home.join(".config")));
+                if let Some(xdg) = xdg {
+                    files.push((ConfigScope::Xdg, xdg.join("git").join("config")));
+                }
+                if let Some(home) = dirs::home_dir() {
+                    files.push((ConfigScope::Global, home.join(".gitconfig")));
+                }
+            }
+        }
+
+        if let Some(git_dir) = git_dir {
+            files.push((ConfigScope::Local, git_dir.join("config")));
+        }
+
+        Self::from_files(&files, &ctx)
+    }
+
+    /// Loads the given files in order, later files take precedence
+    pub fn from_files(files: &[(ConfigScope, PathBuf)], ctx: &IncludeContext) -> Self {
+        let mut config = GitConfig::default();
+
+        for (scope, path) in files {
+            config.read_file(path, *scope, ctx, 0);
+        }
+
+        config
+    }
+
+    fn read_file(&mut self, path: &Path, scope: ConfigScope, ctx: &IncludeContext, depth: usize) {
+        if depth > MAX_INCLUDE_DEPTH {
+            return;
+        }
+
+        let Ok(contents) = fs::read_to_string(path) else {
+            return;
+        };
+
+        for (key, value, line) in parse(&contents) {
+            let include = include_path(&key, &value, path, ctx);
+
+            self.entries.push(ConfigEntry {
+                key,
+                value,
+                scope,
+                origin: path.to_path_buf(),
+                line,
+            });
+
+            // included files are read in place, so their values override earlier ones
+            // and are overridden by anything after the include
+            if let Some(include) = include {
+                self.read_file(&include, scope, ctx, depth + 1);
+            }
+        }
+    }
+
+    /// Gets the effective value of a key, the last one git read wins
+    ///
+    /// # Arguments
+    /// * `key` - the key to look for -> e.g. `user.name`, section and name are case insensitive
+    pub fn get(&self, key: &str) -> Option<&ConfigEntry> {
+        let key = normalize_key(key);
+        self.entries.iter().rev().find(|entry| entry.key == key)
+    }
+
+    /// Gets every value of a multi-valued key, in the order git read them
+    pub fn get_all(&self, key: &str) -> Vec<&ConfigEntry> {
+        let key = normalize_key(key);
+        self.entries
+            .iter()
+            .filter(|entry| entry.key == key)
+            .collect()
+    }
+}
+
+/// Lowercases the section and variable name of a key, subsections are case sensitive
+fn normalize_key(key: &str) -> String {
+    let Some((section, rest)) = key.split_once('.') else {
+        return key.to_lowercase();
+    };
+
+    match rest.rsplit_once('.') {
+        Some((subsection, name)) => format!(
+            "{}.{}.{}",
+            section.to_lowercase(),
+            subsection,
+            name.to_lowercase()
+        ),
+        None => format!("{}.{}", section.to_lowercase(), rest.to_lowercase()),
+    }
+}
+
+/// Parses the contents of a git config file
+///
+/// # Returns
+/// * every `(normalized key, value, line number)` in the file, in order
+fn parse(contents: &str) -> Vec<(String, String, usize)> {
+    let mut entries = Vec::new();
+    let mut section = String::new();
+
+    let mut lines = contents.lines().enumerate();
+
+    while let Some((index, raw_line)) = lines.next() {
+        let mut line = raw_line.trim_start();
+
+        if line.starts_with('[') {
+            let Some((header, rest)) = parse_section_header(line) else {
+                continue;
+            };
+            section = header;
+            line = rest.trim_start();
+        }
+
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') || section.is_empty() {
+            continue;
+        }
+
+        let name_len = line
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+            .unwrap_or(line.len());
+        let (name, rest) = line.split_at(name_len);
+        if name.is_empty() {
+            continue;
+        }
+
+        let key = format!("{}.{}", section, name.to_lowercase());
+        let rest = rest.trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            Some(raw_value) => {
+                let mut raw_value = raw_value.to_owned();
+                // a trailing backslash continues the value on the next line
+                while ends_with_continuation(&raw_value) {
+                    raw_value.pop();
+                    match lines.next() {
+                        Some((_, next)) => raw_value.push_str(next),
+                        None => break,
+                    }
+                }
+                parse_value(&raw_value)
+            }
+            // a bare key is a boolean set to true
+            None => "true".to_owned(),
+        };
+
+        entries.push((key, value, index + 1));
+    }
+
+    entries
+}
+
+/// Parses `[section]`, `[section "subsection"]` or the legacy `[section.subsection]`
+///
+/// # Returns
+/// * `Some((header, rest))` with the normalized section prefix and whatever follows the `]`
+/// * `None` if the header is malformed
+fn parse_section_header(line: &str) -> Option<(String, &str)> {
+    let inner = line.strip_prefix('[')?;
+
+    if let Some(quote) = inner.find('"') {
+        let section = inner[..quote].trim().to_lowercase();
+        let mut subsection = String::new();
+        let mut chars = inner[quote + 1..].char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => subsection.push(chars.next()?.1),
+                '"' => {
+                    let after = inner[quote + 1 + i + 1..].trim_start().strip_prefix(']')?;
+                    return Some((format!("{}.{}", section, subsection), after));
+                }
+                c => subsection.push(c),
+            }
+        }
+        return None;
+    }
+
+    let (header, rest) = inner.split_once(']')?;
+    // legacy subsections are case insensitive, so lowercasing everything is right
+    Some((header.trim().to_lowercase(), rest))
+}
+
+fn ends_with_continuation(raw_value: &str) -> bool {
+    let trailing = raw_value.len() - raw_value.trim_end_matches('\\').len();
+    trailing % 2 == 1
+}
+
+/// Unquotes and unescapes a raw value, dropping trailing comments and whitespace
+fn parse_value(raw: &str) -> String {
+    let mut value = String::new();
+    let mut in_quotes = false;
+    // whitespace is only kept if something follows it
+    let mut pending_space = String::new();
+    let mut chars = raw.trim_start().chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                value.push_str(&std::mem::take(&mut pending_space));
+                in_quotes = !in_quotes;
+            }
+            '\\' => {
+                value.push_str(&std::mem::take(&mut pending_space));
+                match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('b') => value.push('\u{8}'),
+                    Some(other) => value.push(other),
+                    None => {}
+                }
+            }
+            '#' | ';' if !in_quotes => break,
+            c if c.is_whitespace() && !in_quotes => pending_space.push(' '),
+            c => {
+                value.push_str(&std::mem::take(&mut pending_space));
+                value.push(c);
+            }
+        }
+    }
+
+    value
+}
+
+/// Works out which file an `include.path` or `includeIf.<condition>.path` entry pulls in
+///
+/// # Returns
+/// * `Some(PathBuf)` if the entry is an include whose condition holds
+/// * `None` otherwise
+fn include_path(key: &str, value: &str, origin: &Path, ctx: &IncludeContext) -> Option<PathBuf> {
+    let condition = if key == "include.path" {
+        None
+    } else {
+        let condition = key.strip_prefix("includeif.")?.strip_suffix(".path")?;
+        Some(condition)
+    };
+
+    if let Some(condition) = condition {
+        if !condition_matches(condition, origin, ctx) {
+            return None;
+        }
+    }
+
+    let base = origin.parent().unwrap_or(Path::new("."));
+    Some(resolve_path(value, base))
+}
+
+fn condition_matches(condition: &str, origin: &Path, ctx: &IncludeContext) -> bool {
+    if let Some(pattern) = condition.strip_prefix("gitdir:") {
+        return gitdir_matches(pattern, origin, ctx, false);
+    }
+    if let Some(pattern) = condition.strip_prefix("gitdir/i:") {
+        return gitdir_matches(pattern, origin, ctx, true);
+    }
+    if let Some(pattern) = condition.strip_prefix("onbranch:") {
+        let Some(branch) = &ctx.branch else {
+            return false;
+        };
+        let pattern = if pattern.ends_with('/') {
+            format!("{}**", pattern)
+        } else {
+            pattern.to_owned()
+        };
+        return glob_match(&pattern, branch);
+    }
+
+    // hasconfig: and anything newer is not supported, git would also skip unknown conditions
+    false
+}
+
+fn gitdir_matches(pattern: &str, origin: &Path, ctx: &IncludeContext, ignore_case: bool) -> bool {
+    let Some(git_dir) = &ctx.git_dir else {
+        return false;
+    };
+
+    let mut pattern = if let Some(relative) = pattern.strip_prefix("./") {
+        let base = origin.parent().unwrap_or(Path::new("."));
+        base.join(relative).to_string_lossy().to_string()
+    } else if pattern.starts_with("~/") {
+        resolve_path(pattern, Path::new("/"))
+            .to_string_lossy()
+            .to_string()
+    } else {
+        pattern.to_owned()
+    };
+
+    if !pattern.starts_with('/') && !pattern.starts_with("**/") {
+        pattern = format!("**/{}", pattern);
+    }
+    if pattern.ends_with('/') {
+        pattern.push_str("**");
+    }
+
+    // git matches against the .git directory itself, written with a trailing slash
+    let mut git_dir = git_dir.to_string_lossy().to_string();
+    if !git_dir.ends_with('/') {
+        git_dir.push('/');
+    }
+
+    if ignore_case {
+        pattern = pattern.to_lowercase();
+        git_dir = git_dir.to_lowercase();
+    }
+
+    glob_match(&pattern, &git_dir) || glob_match(&pattern, git_dir.trim_end_matches('/'))
+}
+
+/// Expands `~/` and makes relative paths relative to `base`
+fn resolve_path(path: &str, base: &Path) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+
+    let path = Path::new(path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    }
+}
+
+/// Wildcard matching for include conditions
+///
+/// `*` and `?` do not cross `/`, `**` matches across directories
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_from(&pattern, &text)
+}
+
+fn glob_match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            // `**/` can also match no directories at all
+            let rest = &pattern[2..];
+            let rest_without_slash = rest.strip_prefix(&['/']).unwrap_or(rest);
+            (0..=text.len()).any(|i| {
+                glob_match_from(rest, &text[i..]) || glob_match_from(rest_without_slash, &text[i..])
+            })
+        }
+        Some('*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match_from(&pattern[1..], &text[i..])),
+        Some('?') => {
+            !text.is_empty() && text[0] != '/' && glob_match_from(&pattern[1..], &text[1..])
+        }
+        Some(&c) => !text.is_empty() && text[0] == c && glob_match_from(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Finds the `.git` directory of the repository containing `dir`
+///
+/// Follows `.git` files (`gitdir: ...`) used by worktrees and submodules
+pub fn find_git_dir(dir: &Path) -> Option<PathBuf> {
+    let dir = dir.canonicalize().ok()?;
+
+    for ancestor in dir.ancestors() {
+        let candidate = ancestor.join(".git");
+
+        if candidate.is_dir() {
+            return Some(candidate);
+        }
+
+        if candidate.is_file() {
+            let contents = fs::read_to_string(&candidate).ok()?;
+            let target = contents.trim().strip_prefix("gitdir:")?.trim();
+            return Some(resolve_path(target, ancestor));
+        }
+    }
+
+    None
+}
+
+/// Reads the checked out branch from `HEAD`, `None` when detached
+fn current_branch(git_dir: &Path) -> Option<String> {
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    head.trim()
+        .strip_prefix("ref: refs/heads/")
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_sections_and_values() {
+        let entries = parse(
+            r#"
+            [user]
+                email = john.doe@ucla.edu
+                name = "Johnathan D" ; display name
+            [core] editor = /usr/bin/vim
+                bare
+            [remote "Origin"]
+                url = git@github.com:oseda-dev/oseda-lib.git
+            [alias]
+                quoted = "echo \"hi\"\t# not a comment"
+            "#,
+        );
+
+        let get = |key: &str| {
+            entries
+                .iter()
+                .find(|(k, _, _)| k == key)
+                .map(|(_, v, _)| v.as_str())
+        };
+
+        assert_eq!(get("user.email"), Some("john.doe@ucla.edu"));
+        assert_eq!(get("user.name"), Some("Johnathan D"));
+        assert_eq!(get("core.editor"), Some("/usr/bin/vim"));
+        assert_eq!(get("core.bare"), Some("true"));
+        assert_eq!(
+            get("remote.Origin.url"),
+            Some("git@github.com:oseda-dev/oseda-lib.git")
+        );
+        assert_eq!(get("alias.quoted"), Some("echo \"hi\"\t# not a comment"));
+    }
+
+    #[test]
+    fn test_parse_continuation_lines() {
+        let entries = parse("[alias]\n\tlg = log \\\n--oneline\n");
+
+        assert_eq!(
+            entries,
+            vec![("alias.lg".to_owned(), "log --oneline".to_owned(), 2)]
+        );
+    }
+
+    #[test]
+    fn test_last_value_wins_across_scopes() {
+        let dir = tempdir().unwrap();
+        let global = dir.path().join("gitconfig");
+        let local = dir.path().join("config");
+        fs::write(&global, "[user]\n\tname = DisplayName\n\temail = a@b.c\n").unwrap();
+        fs::write(&local, "[User]\n\tName = ReeseHatfield\n").unwrap();
+
+        let config = GitConfig::from_files(
+            &[
+                (ConfigScope::Global, global.clone()),
+                (ConfigScope::Local, local.clone()),
+            ],
+            &IncludeContext::default(),
+        );
+
+        let name = config.get("user.name").unwrap();
+        assert_eq!(name.value, "ReeseHatfield");
+        assert_eq!(name.scope, ConfigScope::Local);
+        assert_eq!(name.origin, local);
+        assert_eq!(name.line, 2);
+
+        assert_eq!(config.get("USER.EMAIL").unwrap().origin, global);
+        assert_eq!(config.get_all("user.name").len(), 2);
+    }
+
+    #[test]
+    fn test_include_and_include_if() {
+        let dir = tempdir().unwrap();
+        let work = dir.path().join("work.inc");
+        let school = dir.path().join("school.inc");
+        let global = dir.path().join("gitconfig");
+
+        fs::write(&work, "[user]\n\tname = WorkName\n").unwrap();
+        fs::write(&school, "[user]\n\tname = SchoolName\n").unwrap();
+        fs::write(
+            &global,
+            "[user]\n\tname = Base\n[include]\n\tpath = work.inc\n[includeIf \"gitdir:/courses/\"]\n\tpath = school.inc\n",
+        )
+        .unwrap();
+
+        let files = [(ConfigScope::Global, global.clone())];
+
+        let outside = GitConfig::from_files(
+            &files,
+            &IncludeContext {
+                git_dir: Some(PathBuf::from("/home/jane/other/.git")),
+                branch: None,
+            },
+        );
+        let name = outside.get("user.name").unwrap();
+        assert_eq!(name.value, "WorkName");
+        assert_eq!(name.origin, work);
+        assert_eq!(name.scope, ConfigScope::Global);
+
+        let inside = GitConfig::from_files(
+            &files,
+            &IncludeContext {
+                git_dir: Some(PathBuf::from("/courses/IntroToRust/.git")),
+                branch: None,
+            },
+        );
+        assert_eq!(inside.get("user.name").unwrap().value, "SchoolName");
+    }
+
+    #[test]
+    fn test_include_if_onbranch() {
+        let ctx = IncludeContext {
+            git_dir: None,
+            branch: Some("course/IntroToRust".to_owned()),
+        };
+
+        assert!(condition_matches("onbranch:course/", Path::new("/x"), &ctx));
+        assert!(condition_matches(
+            "onbranch:course/*",
+            Path::new("/x"),
+            &ctx
+        ));
+        assert!(!condition_matches("onbranch:main", Path::new("/x"), &ctx));
+    }
+
+    #[test]
+    fn test_include_if_gitdir_ignore_case() {
+        let ctx = IncludeContext {
+            git_dir: Some(PathBuf::from("/Users/Jane/Courses/IntroToRust/.git")),
+            branch: None,
+        };
+        let origin = Path::new("/x");
+
+        assert!(condition_matches(
+            "gitdir/i:/users/jane/courses/introtorust/.git",
+            origin,
+            &ctx
+        ));
+        assert!(condition_matches("gitdir/i:**/COURSES/**", origin, &ctx));
+        assert!(!condition_matches(
+            "gitdir:/users/jane/courses/introtorust/.git",
+            origin,
+            &ctx
+        ));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("**/work/**", "/home/jane/work/course/.git/"));
+        assert!(glob_match("/home/*/work/**", "/home/jane/work/.git/"));
+        assert!(!glob_match("/home/*/work/**", "/home/jane/x/work/.git/"));
+        assert!(glob_match("**/.git", "/a/.git"));
+    }
+}
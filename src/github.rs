@@ -1,12 +1,17 @@
-/*
-user.email=hatfield.69@wright.edu
-user.name=ReeseHatfield
-core.editor=/usr/bin/vim
-*/
+use std::error::Error;
 
-use std::{error::Error, path::Path, process::Command};
+use reqwest::{blocking::RequestBuilder, Method, StatusCode};
+use serde::Deserialize;
 
-/// Gets a value from the users local git configuration, see example
+use crate::{
+    gitconfig::GitConfig,
+    library::{HostKind, LibraryConfig},
+};
+
+/// Gets a value from the users git configuration, see example
+///
+/// Reads the config files directly, so repo-local values and includes are resolved with
+/// git's precedence. See `gitconfig::GitConfig` to also find out where a value is set
 ///
 /// # Arguments
 /// * `key` - the config key to look for -> e.g core.editor
@@ -22,44 +27,164 @@ use std::{error::Error, path::Path, process::Command};
 /// let name = get_config_from_user_git("user.name");
 /// ```
 pub fn get_config_from_user_git(key: &str) -> Option<String> {
-    let handle = Command::new("git")
-        .arg("config")
-        .arg("--list")
-        .output()
-        .ok()?;
+    let cwd = std::env::current_dir().ok()?;
+
+    GitConfig::load(&cwd)
+        .get(key)
+        .map(|entry| entry.value.clone())
+}
+
+/// Looks for a GitHub API token in the environment
+///
+/// # Returns
+/// * `Some(String)` with the value of `GITHUB_TOKEN`, or `GH_TOKEN` if that is unset
+/// * `None` if neither is set
+pub fn token_from_env() -> Option<String> {
+    ["GITHUB_TOKEN", "GH_TOKEN"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|token| !token.is_empty()))
+}
 
-    let conf_out = String::from_utf8(handle.stdout).ok()?;
+/// The parts of a GitHub repository response oseda cares about
+#[derive(Deserialize, Debug)]
+pub struct Repository {
+    pub full_name: String,
+    pub clone_url: String,
+    pub html_url: String,
+    pub default_branch: String,
+    #[serde(default)]
+    pub fork: bool,
+    pub parent: Option<RepositoryRef>,
+}
 
-    get_key_from_conf(key, &conf_out)
+/// Minimal reference to a repository, used for a fork's parent
+#[derive(Deserialize, Debug)]
+pub struct RepositoryRef {
+    pub full_name: String,
 }
 
-fn get_key_from_conf(key: &str, conf: &str) -> Option<String> {
-    conf.split("\n").map(|s| s.trim()).find_map(|line| {
-        let (cur_key, value) = line.split_once('=')?;
-        if cur_key == key {
-            Some(value.to_string())
-        } else {
-            None
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+/// Small blocking client for the GitHub REST API
+pub struct GithubApi {
+    base_url: String,
+    token: String,
+}
+
+impl GithubApi {
+    /// Creates a client for the host the library lives on
+    ///
+    /// # Arguments
+    /// * `library` - the upstream library, must be hosted on GitHub or GitHub Enterprise
+    /// * `token` - a personal access token allowed to create repositories
+    ///
+    /// # Returns
+    /// * `Ok(GithubApi)` for the library host
+    /// * `Err` if the library is not hosted on GitHub
+    pub fn new(library: &LibraryConfig, token: String) -> Result<Self, Box<dyn Error>> {
+        if library.kind != HostKind::GitHub {
+            return Err(format!(
+                "Forking through the API is only supported for GitHub, {} is a {} host",
+                library.host, library.kind
+            )
+            .into());
         }
-    })
+
+        Ok(GithubApi {
+            base_url: api_base_url(&library.host),
+            token,
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        reqwest::blocking::Client::new()
+            .request(method, format!("{}{}", self.base_url, path))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            // GitHub rejects requests without a user agent
+            .header("User-Agent", "oseda-cli")
+    }
+
+    /// Gets the login of the user owning the token
+    pub fn login(&self) -> Result<String, Box<dyn Error>> {
+        let response = self.request(Method::GET, "/user").send()?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "Could not authenticate with GitHub ({}), please check your token",
+                response.status()
+            )
+            .into());
+        }
+
+        let user: User = serde_json::from_str(&response.text()?)?;
+        Ok(user.login)
+    }
+
+    /// Looks up a repository
+    ///
+    /// # Returns
+    /// * `Ok(Some(Repository))` if the repository exists
+    /// * `Ok(None)` if it does not exist (or the token cannot see it)
+    /// * `Err` if the request fails
+    pub fn get_repo(&self, owner: &str, repo: &str) -> Result<Option<Repository>, Box<dyn Error>> {
+        let response = self
+            .request(Method::GET, &format!("/repos/{}/{}", owner, repo))
+            .send()?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(serde_json::from_str(&response.text()?)?)),
+            status => Err(format!("GitHub returned {status} looking up {owner}/{repo}").into()),
+        }
+    }
+
+    /// Checks if a branch exists on a repository, used to tell when a new fork is ready
+    pub fn branch_exists(&self, full_name: &str, branch: &str) -> Result<bool, Box<dyn Error>> {
+        let response = self
+            .request(
+                Method::GET,
+                &format!("/repos/{}/branches/{}", full_name, branch),
+            )
+            .send()?;
+
+        Ok(response.status().is_success())
+    }
+
+    /// Asks GitHub to fork a repository into the token owner's account
+    ///
+    /// GitHub creates forks asynchronously, the returned repository may not be
+    /// ready to clone yet
+    pub fn create_fork(&self, owner: &str, repo: &str) -> Result<Repository, Box<dyn Error>> {
+        let response = self
+            .request(Method::POST, &format!("/repos/{}/{}/forks", owner, repo))
+            .body("{}")
+            .send()?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!(
+                "GitHub returned {status} while forking {owner}/{repo}: {}",
+                response.text().unwrap_or_default()
+            )
+            .into());
+        }
+
+        Ok(serde_json::from_str(&response.text()?)?)
+    }
 }
 
-/// Super generic run git func for general usecases git commands
-///
-/// # Arguments
-/// * `dir` - the directory to run the git command in
-/// * `args` - the list of arguments to pass to git -> e.g. `["clone", "[URL]" ]`
-///
-/// # Returns (based on git exit code)
-/// * `Ok(())` if the git command succeeds
-/// * `Err` if the command fails
-pub fn git(dir: &Path, args: &[&str]) -> Result<(), Box<dyn Error>> {
-    let status = Command::new("git").current_dir(dir).args(args).status()?;
-
-    if !status.success() {
-        return Err(format!("git {:?} failed", args).into());
+/// GitHub.com serves its API from a separate host, GitHub Enterprise serves it under `/api/v3`
+fn api_base_url(host: &str) -> String {
+    if host == "github.com" {
+        "https://api.github.com".to_owned()
+    } else {
+        format!("https://{}/api/v3", host)
     }
-    Ok(())
 }
 
 #[cfg(test)]
@@ -67,21 +192,11 @@ mod test {
     use super::*;
 
     #[test]
-    fn test_git_config_kv_pair() {
-        let config = r#"
-            user.email=john.doe@ucla.edu
-            user.name=JohnathanD
-            core.editor=/usr/bin/vim
-            core.filemode=true
-            core.bare=false
-            core.logallrefupdates=true
-        "#
-        .to_string();
-
-        let email = get_key_from_conf("user.email", &config);
-
-        assert!(email.is_some());
-
-        assert_eq!(email.unwrap(), "john.doe@ucla.edu");
+    fn test_api_base_url() {
+        assert_eq!(api_base_url("github.com"), "https://api.github.com");
+        assert_eq!(
+            api_base_url("github.example.edu"),
+            "https://github.example.edu/api/v3"
+        );
     }
 }
@@ -0,0 +1,348 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{Mutex, OnceLock},
+};
+
+use crate::{
+    gitconfig::GitConfig,
+    github::{self, GithubApi},
+    library::LibraryConfig,
+};
+
+/// Where a GitHub login was found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdentitySource {
+    /// `github.login` in the git config
+    Setting,
+    /// the owner of `GITHUB_TOKEN` / `GH_TOKEN`
+    Token,
+    /// the account the SSH key is registered to
+    SshKey,
+}
+
+impl fmt::Display for IdentitySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Setting => write!(f, "git config github.login"),
+            Self::Token => write!(f, "the GitHub API token"),
+            Self::SshKey => write!(f, "your SSH key"),
+        }
+    }
+}
+
+/// A login on the library's git host, and how we know it
+#[derive(Debug, Clone, PartialEq)]
+pub struct GithubIdentity {
+    pub login: String,
+    pub source: IdentitySource,
+}
+
+/// Ways of finding out who the user is on the git host
+///
+/// The real probes talk to git config, the API and ssh, tests swap in stubs
+pub trait IdentityProbe {
+    /// An explicit `github.login` setting
+    fn configured_login(&self) -> Option<String>;
+
+    /// The login owning the API token in the environment, if there is one
+    fn token_login(&self) -> Option<String>;
+
+    /// Everything `ssh -T git@<host>` printed
+    fn ssh_greeting(&self) -> Option<String>;
+}
+
+/// Resolves the login using the first probe that gives an answer
+///
+/// An explicit setting always wins, then the API token, then the SSH key.
+/// The SSH probe is the slowest so it goes last
+///
+/// # Returns
+/// * `Some(GithubIdentity)` if any source knows the login
+/// * `None` if the user could not be identified
+pub fn resolve(probe: &dyn IdentityProbe) -> Option<GithubIdentity> {
+    if let Some(login) = probe.configured_login().filter(|l| !l.trim().is_empty()) {
+        return Some(GithubIdentity {
+            login: login.trim().to_owned(),
+            source: IdentitySource::Setting,
+        });
+    }
+
+    if let Some(login) = probe.token_login() {
+        return Some(GithubIdentity {
+            login,
+            source: IdentitySource::Token,
+        });
+    }
+
+    probe
+        .ssh_greeting()
+        .and_then(|greeting| parse_ssh_greeting(&greeting))
+        .map(|login| GithubIdentity {
+            login,
+            source: IdentitySource::SshKey,
+        })
+}
+
+/// Gets the login from what a git host says when you `ssh -T` into it
+///
+/// Handles GitHub (`Hi octocat! You've successfully authenticated...`), Gitea
+/// (`Hi there, octocat! You've successfully authenticated...`) and GitLab
+/// (`Welcome to GitLab, @octocat!`)
+///
+/// # Returns
+/// * `Some(String)` with the login
+/// * `None` if the key was rejected, or belongs to a deploy key (`Hi owner/repo!`)
+pub fn parse_ssh_greeting(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let line = line.trim();
+
+        let login = if let Some(rest) = line.strip_prefix("Hi there, ") {
+            rest.split_once('!')?.0
+        } else if let Some(rest) = line.strip_prefix("Hi ") {
+            rest.split_once('!')?.0
+        } else if let Some(rest) = line.strip_prefix("Welcome to GitLab, @") {
+            rest.split_once('!')?.0
+        } else {
+            return None;
+        };
+
+        // deploy keys greet with the repository they belong to, not a user
+        if login.is_empty() || login.contains('/') || login.contains(char::is_whitespace) {
+            return None;
+        }
+
+        Some(login.to_owned())
+    })
+}
+
+/// Logins on GitHub are case insensitive, `JaneDoe` and `janedoe` are the same account
+pub fn logins_match(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+/// Answers from the git host, per host, so deploy --all only asks each host once
+type HostCache = OnceLock<Mutex<HashMap<String, Option<String>>>>;
+
+/// Gets the cached answer for `host`, or asks with `probe` and caches it
+fn cached(cache: &HostCache, host: &str, probe: impl FnOnce() -> Option<String>) -> Option<String> {
+    let cache = cache.get_or_init(Default::default);
+
+    if let Some(answer) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(host) {
+        return answer.clone();
+    }
+
+    // not holding the lock while probing, a slow host shouldn't block the others
+    let answer = probe();
+    cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(host.to_owned(), answer.clone());
+    answer
+}
+
+/// Probes the real git config, API and ssh for a project directory
+pub struct SystemProbe {
+    dir: PathBuf,
+    library: LibraryConfig,
+}
+
+impl SystemProbe {
+    pub fn new(dir: &Path, library: &LibraryConfig) -> Self {
+        SystemProbe {
+            dir: dir.to_owned(),
+            library: library.clone(),
+        }
+    }
+}
+
+impl IdentityProbe for SystemProbe {
+    fn configured_login(&self) -> Option<String> {
+        GitConfig::load(&self.dir)
+            .get("github.login")
+            .map(|entry| entry.value.clone())
+    }
+
+    fn token_login(&self) -> Option<String> {
+        // deploy --all validates every course, only ask the API once per host
+        static LOGINS: HostCache = OnceLock::new();
+
+        cached(&LOGINS, &self.library.host, || {
+            let token = github::token_from_env()?;
+            GithubApi::new(&self.library, token).ok()?.login().ok()
+        })
+    }
+
+    fn ssh_greeting(&self) -> Option<String> {
+        static GREETINGS: HostCache = OnceLock::new();
+
+        cached(&GREETINGS, &self.library.host, || {
+            // the host never gives us a shell, so this always "fails", the greeting is on stderr
+            let output = Command::new("ssh")
+                .args([
+                    "-T",
+                    "-o",
+                    "BatchMode=yes",
+                    "-o",
+                    "ConnectTimeout=10",
+                    &format!("git@{}", self.library.host),
+                ])
+                .stdin(Stdio::null())
+                .output()
+                .ok()?;
+
+            Some(format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        })
+    }
+}
+
+/// Works out the user's login on the library host from their setup
+///
+/// # Arguments
+/// * `dir` - the project directory, repo-local git config applies
+/// * `library` - the library the course deploys to
+pub fn github_login(dir: &Path, library: &LibraryConfig) -> Option<GithubIdentity> {
+    resolve(&SystemProbe::new(dir, library))
+}
+
+/// The login from an explicit `github.login` setting, never asks the git host
+///
+/// # Arguments
+/// * `dir` - the project directory, repo-local git config applies
+pub fn configured_login(dir: &Path) -> Option<GithubIdentity> {
+    let probe = SystemProbe::new(dir, &LibraryConfig::default());
+
+    probe
+        .configured_login()
+        .filter(|login| !login.trim().is_empty())
+        .map(|login| GithubIdentity {
+            login: login.trim().to_owned(),
+            source: IdentitySource::Setting,
+        })
+}
+
+/// How the user can tell oseda who they are, for error messages
+pub fn login_hint() -> &'static str {
+    "Set your login with `git config --global github.login <your github username>`, \
+     set GITHUB_TOKEN, or add your SSH key to GitHub"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct StubProbe {
+        setting: Option<&'static str>,
+        token: Option<&'static str>,
+        ssh: Option<&'static str>,
+    }
+
+    impl IdentityProbe for StubProbe {
+        fn configured_login(&self) -> Option<String> {
+            self.setting.map(str::to_owned)
+        }
+
+        fn token_login(&self) -> Option<String> {
+            self.token.map(str::to_owned)
+        }
+
+        fn ssh_greeting(&self) -> Option<String> {
+            self.ssh.map(str::to_owned)
+        }
+    }
+
+    #[test]
+    fn test_parse_ssh_greeting() {
+        assert_eq!(
+            parse_ssh_greeting(
+                "Hi JaneDoe! You've successfully authenticated, but GitHub does not provide shell access."
+            ),
+            Some("JaneDoe".to_owned())
+        );
+        assert_eq!(
+            parse_ssh_greeting(
+                "Hi there, jane! You've successfully authenticated with the key named laptop"
+            ),
+            Some("jane".to_owned())
+        );
+        assert_eq!(
+            parse_ssh_greeting("Welcome to GitLab, @jdoe!"),
+            Some("jdoe".to_owned())
+        );
+        assert_eq!(
+            parse_ssh_greeting("Hi oseda-dev/oseda-lib! You've successfully authenticated"),
+            None
+        );
+        assert_eq!(
+            parse_ssh_greeting("git@github.com: Permission denied (publickey)."),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let greeting = "Hi SshUser! You've successfully authenticated";
+
+        let identity = resolve(&StubProbe {
+            setting: Some("SettingUser"),
+            token: Some("TokenUser"),
+            ssh: Some(greeting),
+        })
+        .unwrap();
+        assert_eq!(identity.login, "SettingUser");
+        assert_eq!(identity.source, IdentitySource::Setting);
+
+        let identity = resolve(&StubProbe {
+            setting: None,
+            token: Some("TokenUser"),
+            ssh: Some(greeting),
+        })
+        .unwrap();
+        assert_eq!(identity.source, IdentitySource::Token);
+
+        let identity = resolve(&StubProbe {
+            setting: Some("  "),
+            token: None,
+            ssh: Some(greeting),
+        })
+        .unwrap();
+        assert_eq!(identity.login, "SshUser");
+        assert_eq!(identity.source, IdentitySource::SshKey);
+
+        assert!(resolve(&StubProbe {
+            setting: None,
+            token: None,
+            ssh: Some("Permission denied (publickey)."),
+        })
+        .is_none());
+
+        assert!(logins_match("JaneDoe", "janedoe"));
+    }
+
+    #[test]
+    fn test_cache_is_per_host() {
+        let cache: HostCache = OnceLock::new();
+
+        assert_eq!(
+            cached(&cache, "github.com", || Some("JaneDoe".to_owned())),
+            Some("JaneDoe".to_owned())
+        );
+        // answered already, not asked again
+        assert_eq!(
+            cached(&cache, "github.com", || panic!("asked twice")),
+            Some("JaneDoe".to_owned())
+        );
+        // another library host gets its own answer
+        assert_eq!(cached(&cache, "gitlab.example.edu", || None), None);
+        assert_eq!(
+            cached(&cache, "github.com", || None),
+            Some("JaneDoe".to_owned())
+        );
+    }
+}
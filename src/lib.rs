@@ -1,12 +1,23 @@
 use clap::{Parser, Subcommand};
 
+pub mod chapters;
 pub mod cmd;
 pub mod color;
 pub mod config;
+pub mod generated;
+pub mod git;
+pub mod gitconfig;
 pub mod github;
+pub mod identity;
+pub mod library;
 pub mod net;
+pub mod package;
+pub mod pm;
+pub mod render;
+pub mod reveal;
 pub mod tags;
 pub mod template;
+pub mod theme;
 
 /// Oseda Project scafolding CLI
 #[derive(Parser)]
@@ -25,14 +36,32 @@ pub struct Cli {
 pub enum Commands {
     /// Initialize a new Oseda project in the working directory
     Init(cmd::init::InitOptions),
+    /// Turn an existing reveal.js deck into an Oseda project
+    Adopt(cmd::adopt::AdoptOptions),
     /// Run the Oseda project in the working directory
     Run,
     /// Check the Oseda project in the working directory for common errors
     Check(cmd::check::CheckOptions),
     /// Deploy your Oseda project to github to add to oseda.net
     Deploy(cmd::deploy::DeployOptions),
+    /// Compare the Oseda project in the working directory against what is deployed
+    Status(cmd::status::StatusOptions),
+    /// Remove your Oseda project from your fork of the library
+    Undeploy(cmd::undeploy::UndeployOptions),
     /// Fork the library repository to submit your course
-    Fork,
+    Fork(cmd::fork::ForkOptions),
+    /// List the templates Oseda projects can be created from
+    Template(cmd::template::TemplateOptions),
+    /// Upgrade the Oseda project to the current version of its template
+    Upgrade(cmd::upgrade::UpgradeOptions),
+    /// Prefetch the packages `oseda init --offline` installs from
+    Cache(cmd::cache::CacheOptions),
+    /// Add or remove reveal.js plugins, like math, diagrams, search and zoom
+    Plugin(cmd::plugin::PluginOptions),
+    /// List themes and switch the Oseda project to another one
+    Theme(cmd::theme::ThemeOptions),
+    /// Install git hooks that check the Oseda project before commits and pushes
+    Hooks(cmd::hooks::HooksOptions),
     /// Export the Oseda project to a PDF file
     /// This will install the npm package `decktape`
     /// This relies on a chromium backend, as a result, it may take a while to run
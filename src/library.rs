@@ -0,0 +1,210 @@
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::config::{self, OsedaConfig};
+
+/// Git forge software hosting the library, decides how fork and PR urls look
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default, Display, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum HostKind {
+    #[default]
+    GitHub,
+    Gitea,
+    GitLab,
+}
+
+/// Upstream library repository courses get deployed to
+///
+/// Defaults to `github.com/oseda-dev/oseda-lib`. Self-hosted OSEDA instances can
+/// override any of these fields in the global config (`~/.config/oseda/config.json`)
+/// or in a project's `oseda-config.json` under `library`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LibraryConfig {
+    pub host: String,
+    pub kind: HostKind,
+    pub owner: String,
+    pub repo: String,
+    pub courses_path: String,
+    pub default_branch: String,
+}
+
+impl Default for LibraryConfig {
+    fn default() -> Self {
+        LibraryConfig {
+            host: "github.com".to_owned(),
+            kind: HostKind::GitHub,
+            owner: "oseda-dev".to_owned(),
+            repo: "oseda-lib".to_owned(),
+            courses_path: "courses".to_owned(),
+            default_branch: "main".to_owned(),
+        }
+    }
+}
+
+impl LibraryConfig {
+    /// Web url of the upstream library -> e.g. `https://github.com/oseda-dev/oseda-lib`
+    pub fn web_url(&self) -> String {
+        format!("https://{}/{}/{}", self.host, self.owner, self.repo)
+    }
+
+    /// Page the user visits to fork the library
+    pub fn fork_url(&self) -> String {
+        match self.kind {
+            HostKind::GitHub | HostKind::Gitea => format!("{}/fork", self.web_url()),
+            HostKind::GitLab => format!("{}/-/forks/new", self.web_url()),
+        }
+    }
+
+    /// Page the user visits to open a pull (or merge) request from a branch on their fork
+    ///
+    /// # Arguments
+    /// * `fork_owner` - the account owning the fork
+    /// * `fork_repo` - the name of the fork repository
+    /// * `branch` - the branch on the fork holding the changes
+    pub fn compare_url(&self, fork_owner: &str, fork_repo: &str, branch: &str) -> String {
+        match self.kind {
+            HostKind::GitHub => format!(
+                "{}/compare/{}...{}:{}:{}?expand=1",
+                self.web_url(),
+                self.default_branch,
+                fork_owner,
+                fork_repo,
+                branch
+            ),
+            HostKind::Gitea => format!(
+                "{}/compare/{}...{}:{}",
+                self.web_url(),
+                self.default_branch,
+                fork_owner,
+                branch
+            ),
+            HostKind::GitLab => format!(
+                "https://{}/{}/{}/-/merge_requests/new?merge_request[source_branch]={}&merge_request[target_branch]={}",
+                self.host, fork_owner, fork_repo, branch, self.default_branch
+            ),
+        }
+    }
+
+    /// Converts an https url on the library host into its ssh form
+    ///
+    /// `https://github.com/ReeseHatfield/oseda-lib-testing/`
+    /// into
+    /// `git@github.com:ReeseHatfield/oseda-lib-testing.git`
+    ///
+    /// # Returns
+    /// * `Ok(String)` with the ssh url
+    /// * `Err` if the url is not on the library host
+    pub fn ssh_url(&self, https_url: &str) -> Result<String, Box<dyn Error>> {
+        let suffix = https_url
+            .strip_prefix(&format!("https://{}/", self.host))
+            .ok_or_else(|| format!("Could not get SSH URL, expected a url on {}", self.host))?;
+
+        Ok(format!(
+            "git@{}:{}.git",
+            self.host,
+            suffix.trim_end_matches('/').trim_end_matches(".git")
+        ))
+    }
+
+    /// Path of a course inside the library repository -> e.g. `courses/IntroToRust`
+    pub fn course_path(&self, course_name: &str) -> String {
+        format!(
+            "{}/{}",
+            self.courses_path.trim_end_matches('/'),
+            course_name
+        )
+    }
+}
+
+/// Resolves which library to use, the project config wins over the global config,
+/// falling back to the public oseda.net library
+///
+/// # Arguments
+/// * `project` - the loaded project config, if running inside a project
+///
+/// # Returns
+/// * `Ok(LibraryConfig)` with the library to use
+/// * `Err` if the global config exists but cannot be parsed
+pub fn resolve(project: Option<&OsedaConfig>) -> Result<LibraryConfig, Box<dyn Error>> {
+    if let Some(library) = project.and_then(|conf| conf.library.clone()) {
+        return Ok(library);
+    }
+
+    Ok(config::read_global_config()?.library.unwrap_or_default())
+}
+
+/// Splits a repository url into its owner and repository name
+///
+/// Works on both https and ssh urls -> e.g. `https://github.com/ReeseHatfield/oseda-lib`
+/// and `git@github.com:ReeseHatfield/oseda-lib.git` both give `("ReeseHatfield", "oseda-lib")`
+pub fn owner_and_repo(url: &str) -> Option<(String, String)> {
+    let path = url.trim_end_matches('/').trim_end_matches(".git");
+    let path = match path.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?.1,
+        None => path.split_once(':')?.1,
+    };
+
+    let (owner, repo) = path.rsplit_once('/')?;
+
+    Some((owner.to_owned(), repo.to_owned()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_library_urls() {
+        let library = LibraryConfig::default();
+
+        assert_eq!(
+            library.fork_url(),
+            "https://github.com/oseda-dev/oseda-lib/fork"
+        );
+        assert_eq!(
+            library.compare_url("ReeseHatfield", "oseda-lib", "course/IntroToRust"),
+            "https://github.com/oseda-dev/oseda-lib/compare/main...ReeseHatfield:oseda-lib:course/IntroToRust?expand=1"
+        );
+        assert_eq!(library.course_path("IntroToRust"), "courses/IntroToRust");
+    }
+
+    #[test]
+    fn test_partial_library_config_uses_defaults() {
+        let library: LibraryConfig =
+            serde_json::from_str(r#"{ "host": "git.example.edu", "kind": "GitLab" }"#).unwrap();
+
+        assert_eq!(library.owner, "oseda-dev");
+        assert_eq!(
+            library.fork_url(),
+            "https://git.example.edu/oseda-dev/oseda-lib/-/forks/new"
+        );
+    }
+
+    #[test]
+    fn test_ssh_url() {
+        let library = LibraryConfig::default();
+
+        assert_eq!(
+            library
+                .ssh_url("https://github.com/ReeseHatfield/oseda-lib-testing/")
+                .unwrap(),
+            "git@github.com:ReeseHatfield/oseda-lib-testing.git"
+        );
+        assert!(library.ssh_url("https://gitlab.com/someone/repo").is_err());
+    }
+
+    #[test]
+    fn test_owner_and_repo() {
+        assert_eq!(
+            owner_and_repo("https://github.com/ReeseHatfield/oseda-lib/"),
+            Some(("ReeseHatfield".to_owned(), "oseda-lib".to_owned()))
+        );
+        assert_eq!(
+            owner_and_repo("git@git.example.edu:group/sub/oseda-lib.git"),
+            Some(("group/sub".to_owned(), "oseda-lib".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::{collections::BTreeMap, error::Error, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::template::TemplateManifest;
+
+/// The packages every Oseda project gets, the one place their versions are pinned
+const BASE_MANIFEST: &str = include_str!("static/package.json");
+
+/// Pinned dependencies and scripts of a project, turned into its package.json
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PackageManifest {
+    /// name -> version, installed as dependencies
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    /// name -> version, installed as devDependencies
+    #[serde(default)]
+    pub dev_dependencies: BTreeMap<String, String>,
+    /// npm scripts -> e.g. `build` -> `vite build`
+    #[serde(default)]
+    pub scripts: BTreeMap<String, String>,
+    /// packages oseda installs on demand rather than with the project -> e.g. `decktape` for export
+    #[serde(default)]
+    pub tools: BTreeMap<String, String>,
+    /// packages the optional reveal.js plugins need, installed by `oseda plugin add` -> e.g. `katex`
+    #[serde(default)]
+    pub plugins: BTreeMap<String, String>,
+}
+
+impl PackageManifest {
+    /// The manifest built into oseda
+    pub fn base() -> PackageManifest {
+        serde_json::from_str(BASE_MANIFEST).expect("static/package.json is valid")
+    }
+
+    /// The base manifest with a template's extra packages and scripts on top
+    ///
+    /// Templates can add packages, or pin a different version of a base package
+    pub fn for_template(template: &TemplateManifest) -> PackageManifest {
+        let mut manifest = PackageManifest::base();
+
+        manifest.dependencies.extend(template.dependencies.clone());
+        manifest
+            .dev_dependencies
+            .extend(template.dev_dependencies.clone());
+        manifest.scripts.extend(template.scripts.clone());
+
+        // a package pinned as a dependency by the template wins over the base dev dependency, and vice versa
+        for name in template.dependencies.keys() {
+            manifest.dev_dependencies.remove(name);
+        }
+        for name in template.dev_dependencies.keys() {
+            manifest.dependencies.remove(name);
+        }
+
+        manifest
+    }
+
+    /// A pinned package spec for a tool -> e.g. `decktape@3.15.0`
+    pub fn tool_spec(&self, name: &str) -> Option<String> {
+        self.tools
+            .get(name)
+            .map(|version| format!("{name}@{version}"))
+    }
+
+    /// Builds the package.json of a project
+    ///
+    /// # Arguments
+    /// * `name` - the course title, lowercased and dashed for the package name
+    pub fn package_json(&self, name: &str) -> serde_json::Value {
+        serde_json::json!({
+            "name": name.to_lowercase().replace(char::is_whitespace, "-"),
+            "version": "1.0.0",
+            "private": true,
+            "scripts": self.scripts,
+            "dependencies": self.dependencies,
+            "devDependencies": self.dev_dependencies,
+        })
+    }
+
+    /// Writes the package.json of a project, ready for a single install
+    pub fn write_package_json(&self, project_dir: &Path, name: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(
+            project_dir.join("package.json"),
+            serde_json::to_string_pretty(&self.package_json(name))? + "\n",
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reveal::RevealPlugin;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_base_manifest() {
+        let base = PackageManifest::base();
+
+        assert!(base.dev_dependencies.contains_key("vite"));
+        assert!(base.dependencies.contains_key("reveal.js"));
+        assert_eq!(base.scripts["build"], "vite build");
+        assert_eq!(base.tool_spec("decktape").unwrap(), "decktape@3.15.0");
+        assert_eq!(base.tool_spec("puppeteer"), None);
+
+        // every package a plugin needs is pinned
+        for plugin in RevealPlugin::iter() {
+            for name in plugin.packages() {
+                assert!(base.plugins.contains_key(*name), "{name} is not pinned");
+            }
+        }
+    }
+
+    #[test]
+    fn test_template_extras_and_package_json() {
+        let template: TemplateManifest = serde_json::from_str(
+            r#"{
+                "name": "department",
+                "version": "0.2.0",
+                "files": [],
+                "dependencies": { "katex": "0.16.22", "vite": "6.0.0" },
+                "scripts": { "lint": "markdownlint slides" }
+            }"#,
+        )
+        .unwrap();
+
+        let manifest = PackageManifest::for_template(&template);
+        assert_eq!(manifest.dependencies["katex"], "0.16.22");
+        assert_eq!(manifest.dependencies["vite"], "6.0.0");
+        assert!(!manifest.dev_dependencies.contains_key("vite"));
+        assert_eq!(manifest.scripts["lint"], "markdownlint slides");
+        assert_eq!(manifest.scripts["serve"], "serve dist");
+
+        let package_json = manifest.package_json("Intro To Rust");
+        assert_eq!(package_json["name"], "intro-to-rust");
+        assert_eq!(package_json["dependencies"]["reveal.js"], "5.2.1");
+        assert_eq!(package_json["devDependencies"]["http-server"], "14.1.1");
+        assert_eq!(package_json["scripts"]["build"], "vite build");
+    }
+}
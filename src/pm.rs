@@ -0,0 +1,287 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+use crate::config::OsedaConfig;
+
+/// The JavaScript package managers Oseda projects can use
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default, Display, EnumIter, EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum PackageManager {
+    #[default]
+    Npm,
+    Pnpm,
+    Yarn,
+    Bun,
+}
+
+impl PackageManager {
+    /// The binary to run -> e.g. `pnpm`
+    pub fn program(self) -> &'static str {
+        match self {
+            Self::Npm => "npm",
+            Self::Pnpm => "pnpm",
+            Self::Yarn => "yarn",
+            Self::Bun => "bun",
+        }
+    }
+
+    /// Lockfiles this manager writes, the first one is what current versions use
+    pub fn lockfiles(self) -> &'static [&'static str] {
+        match self {
+            Self::Npm => &["package-lock.json"],
+            Self::Pnpm => &["pnpm-lock.yaml"],
+            Self::Yarn => &["yarn.lock"],
+            Self::Bun => &["bun.lock", "bun.lockb"],
+        }
+    }
+
+    /// Arguments to add packages to package.json and install them
+    ///
+    /// # Arguments
+    /// * `packages` - package specs -> e.g. `vite@5.4.21`
+    /// * `dev` - save them as dev dependencies
+    pub fn add_args(self, packages: &[String], dev: bool) -> Vec<String> {
+        let (add, dev_flag) = match self {
+            Self::Npm => ("install", "--save-dev"),
+            Self::Pnpm => ("add", "--save-dev"),
+            Self::Yarn => ("add", "--dev"),
+            Self::Bun => ("add", "--dev"),
+        };
+
+        let mut args = vec![add.to_owned()];
+        if dev {
+            args.push(dev_flag.to_owned());
+        }
+        args.extend(packages.iter().cloned());
+
+        args
+    }
+
+    /// Arguments to install everything already listed in package.json
+    pub fn install_args(self) -> Vec<String> {
+        vec!["install".to_owned()]
+    }
+
+    /// Arguments that make an install use oseda's package cache
+    ///
+    /// Online installs fill the cache, offline installs only read from it
+    ///
+    /// # Arguments
+    /// * `cache_dir` - the cache for this manager, see `package_cache_dir`
+    /// * `offline` - fail instead of downloading anything missing from the cache
+    ///
+    /// # Returns
+    /// * `Ok(Vec<String>)` -> e.g. `["--cache", "~/.cache/oseda/packages/npm", "--offline"]`
+    /// * `Err` if the manager can't install offline
+    pub fn cache_args(
+        self,
+        cache_dir: &Path,
+        offline: bool,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let cache_flag = match self {
+            Self::Npm => "--cache",
+            Self::Pnpm => "--store-dir",
+            // yarn 1 flags, what `yarn` still is on most machines
+            Self::Yarn => "--cache-folder",
+            Self::Bun => "--cache-dir",
+        };
+
+        let mut args = vec![
+            cache_flag.to_owned(),
+            cache_dir.to_string_lossy().into_owned(),
+        ];
+
+        if offline {
+            if self == Self::Bun {
+                return Err(
+                    "bun can't install offline, use --no-install and run `bun install` later"
+                        .into(),
+                );
+            }
+            args.push("--offline".to_owned());
+        }
+
+        Ok(args)
+    }
+
+    /// A command running a binary from the project's dependencies -> e.g. `npx vite build`
+    pub fn exec(self, bin: &str, args: &[&str]) -> Command {
+        let mut command = match self {
+            Self::Npm => Command::new("npx"),
+            Self::Pnpm => {
+                let mut command = Command::new("pnpm");
+                command.arg("exec");
+                command
+            }
+            Self::Yarn => Command::new("yarn"),
+            Self::Bun => {
+                let mut command = Command::new("bun");
+                command.arg("x");
+                command
+            }
+        };
+
+        command.arg(bin).args(args);
+        command
+    }
+
+    /// A command running a script from package.json -> e.g. `npm run build`
+    pub fn run_script(self, script: &str) -> Command {
+        let mut command = Command::new(self.program());
+        command.args(["run", script]);
+        command
+    }
+
+    /// Whether the manager is installed, checked by asking for its version
+    pub fn is_available(self) -> bool {
+        Command::new(self.program())
+            .arg("--version")
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+}
+
+/// Works out which package manager a project uses
+///
+/// In order: the `package_manager` in the config, a lockfile, the `packageManager`
+/// field corepack uses in package.json, and npm otherwise
+///
+/// # Arguments
+/// * `project_dir` - the project to look in
+/// * `conf` - the project's config, if it has one
+pub fn detect(project_dir: &Path, conf: Option<&OsedaConfig>) -> PackageManager {
+    if let Some(pm) = conf.and_then(|conf| conf.package_manager) {
+        return pm;
+    }
+
+    if let Some(pm) = PackageManager::iter().find(|pm| {
+        pm.lockfiles()
+            .iter()
+            .any(|lockfile| project_dir.join(lockfile).exists())
+    }) {
+        return pm;
+    }
+
+    package_json_manager(project_dir).unwrap_or_default()
+}
+
+/// Reads `"packageManager": "pnpm@9.1.0"` from package.json
+fn package_json_manager(project_dir: &Path) -> Option<PackageManager> {
+    let package_json = fs::read_to_string(project_dir.join("package.json")).ok()?;
+    let package_json: serde_json::Value = serde_json::from_str(&package_json).ok()?;
+
+    let spec = package_json.get("packageManager")?.as_str()?;
+    let name = spec.split('@').next()?;
+
+    name.parse().ok()
+}
+
+/// Gets the package cache for a manager -> e.g. `~/.cache/oseda/packages/pnpm`
+///
+/// Can be moved with `OSEDA_CACHE_DIR`
+pub fn package_cache_dir(package_manager: PackageManager) -> Option<PathBuf> {
+    let cache_dir = match std::env::var_os("OSEDA_CACHE_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::cache_dir()?.join("oseda"),
+    };
+
+    Some(cache_dir.join("packages").join(package_manager.program()))
+}
+
+/// Picks a manager for a new project, the first one installed out of npm, pnpm, yarn and bun
+pub fn default_for_new_project() -> PackageManager {
+    PackageManager::iter()
+        .find(|pm| pm.is_available())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_translate_operations() {
+        let packages = vec!["vite@5.4.21".to_owned()];
+
+        assert_eq!(
+            PackageManager::Npm.add_args(&packages, true),
+            vec!["install", "--save-dev", "vite@5.4.21"]
+        );
+        assert_eq!(
+            PackageManager::Pnpm.add_args(&packages, false),
+            vec!["add", "vite@5.4.21"]
+        );
+        assert_eq!(
+            PackageManager::Bun.add_args(&packages, true),
+            vec!["add", "--dev", "vite@5.4.21"]
+        );
+
+        let exec = PackageManager::Pnpm.exec("vite", &["build"]);
+        assert_eq!(exec.get_program(), "pnpm");
+        assert_eq!(
+            exec.get_args().collect::<Vec<_>>(),
+            vec!["exec", "vite", "build"]
+        );
+
+        assert_eq!(
+            "PNPM".parse::<PackageManager>().unwrap(),
+            PackageManager::Pnpm
+        );
+        assert_eq!(PackageManager::Yarn.to_string(), "yarn");
+
+        let cache = Path::new("/tmp/oseda-cache");
+        assert_eq!(
+            PackageManager::Pnpm.cache_args(cache, true).unwrap(),
+            vec!["--store-dir", "/tmp/oseda-cache", "--offline"]
+        );
+        assert_eq!(
+            PackageManager::Npm.cache_args(cache, false).unwrap(),
+            vec!["--cache", "/tmp/oseda-cache"]
+        );
+        assert!(PackageManager::Bun.cache_args(cache, true).is_err());
+    }
+
+    #[test]
+    fn test_detect_package_manager() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(detect(dir.path(), None), PackageManager::Npm);
+
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{ "name": "introtorust", "packageManager": "yarn@4.1.0" }"#,
+        )
+        .unwrap();
+        assert_eq!(detect(dir.path(), None), PackageManager::Yarn);
+
+        fs::write(dir.path().join("bun.lockb"), "").unwrap();
+        assert_eq!(detect(dir.path(), None), PackageManager::Bun);
+
+        let mut conf: OsedaConfig = serde_json::from_str(
+            r##"{
+                "title": "IntroToRust",
+                "author": "JaneDoe",
+                "tags": ["ComputerScience"],
+                "last_updated": "2024-07-10T12:34:56Z",
+                "color": "#000000",
+                "description": "Learn rust",
+                "package_manager": "pnpm"
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(detect(dir.path(), Some(&conf)), PackageManager::Pnpm);
+
+        conf.package_manager = None;
+        assert_eq!(detect(dir.path(), Some(&conf)), PackageManager::Bun);
+    }
+}
@@ -0,0 +1,356 @@
+use std::{collections::BTreeMap, error::Error};
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Text(&'a str),
+    Var(&'a str),
+    If(&'a str),
+    Else,
+    EndIf,
+}
+
+/// A token and the line it starts on, for error messages
+type Spanned<'a> = (Token<'a>, usize);
+
+#[derive(Debug)]
+enum Node<'a> {
+    Text(&'a str),
+    Var(&'a str, usize),
+    If {
+        name: &'a str,
+        then: Vec<Node<'a>>,
+        otherwise: Vec<Node<'a>>,
+    },
+}
+
+/// Renders a template with the given values, a tiny templating engine for scaffolded files
+///
+/// Supports `{{name}}` to insert a value, and `{{#if name}}...{{else}}...{{/if}}`
+/// to keep a part of the file only when a value is set and not empty.
+/// Block tags on a line of their own don't leave an empty line behind
+///
+/// # Arguments
+/// * `source` - the template text
+/// * `vars` - values by name -> e.g. `title` -> `IntroToRust`
+///
+/// # Returns
+/// * `Ok(String)` with every tag replaced
+/// * `Err` if a tag is malformed, a block is not closed, or a value is missing
+///
+/// # Example
+/// ```
+/// use std::collections::BTreeMap;
+/// use oseda_cli::render::render;
+///
+/// let vars = BTreeMap::from([("title".to_owned(), "IntroToRust".to_owned())]);
+/// let page = render("<title>{{title}}</title>", &vars).unwrap();
+/// assert_eq!(page, "<title>IntroToRust</title>");
+/// ```
+pub fn render(source: &str, vars: &BTreeMap<String, String>) -> Result<String, Box<dyn Error>> {
+    render_with(source, vars, |value| value.to_owned())
+}
+
+/// Like `render`, but escapes the values so they can't break or inject markup
+///
+/// For .html files, `--var` and prompt answers can contain anything
+///
+/// # Example
+/// ```
+/// use std::collections::BTreeMap;
+/// use oseda_cli::render::render_html;
+///
+/// let vars = BTreeMap::from([("title".to_owned(), "Rust & <C>".to_owned())]);
+/// let page = render_html("<title>{{title}}</title>", &vars).unwrap();
+/// assert_eq!(page, "<title>Rust &amp; &lt;C&gt;</title>");
+/// ```
+pub fn render_html(
+    source: &str,
+    vars: &BTreeMap<String, String>,
+) -> Result<String, Box<dyn Error>> {
+    render_with(source, vars, escape_html)
+}
+
+fn render_with(
+    source: &str,
+    vars: &BTreeMap<String, String>,
+    escape: fn(&str) -> String,
+) -> Result<String, Box<dyn Error>> {
+    let tokens = strip_standalone(tokenize(source)?);
+
+    let mut tokens = tokens.into_iter();
+    let (nodes, end) = parse(&mut tokens)?;
+    if let Some((token, line)) = end {
+        return Err(format!(
+            "line {line}: {} without a matching {{{{#if}}}}",
+            describe(&token)
+        )
+        .into());
+    }
+
+    let mut out = String::with_capacity(source.len());
+    eval(&nodes, vars, escape, &mut out)?;
+    Ok(out)
+}
+
+/// Escapes the characters that mean something in HTML text and attribute values
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Splits the source into text and tags, with the line each token starts on
+fn tokenize(source: &str) -> Result<Vec<Spanned<'_>>, Box<dyn Error>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push((Token::Text(&rest[..start]), line));
+            line += rest[..start].matches('\n').count();
+        }
+
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| format!("line {line}: {{{{ is never closed with }}}}"))?;
+        let tag = after[..end].trim();
+
+        let token = if let Some(name) = tag.strip_prefix("#if ") {
+            Token::If(name.trim())
+        } else if tag == "else" {
+            Token::Else
+        } else if tag == "/if" {
+            Token::EndIf
+        } else if !tag.is_empty()
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            Token::Var(tag)
+        } else {
+            return Err(format!("line {line}: unknown tag {{{{{tag}}}}}").into());
+        };
+
+        tokens.push((token, line));
+        line += after[..end].matches('\n').count();
+        rest = &after[end + 2..];
+    }
+
+    if !rest.is_empty() {
+        tokens.push((Token::Text(rest), line));
+    }
+
+    Ok(tokens)
+}
+
+/// Removes the whitespace and newline around block tags that sit on a line of their own
+fn strip_standalone(mut tokens: Vec<Spanned<'_>>) -> Vec<Spanned<'_>> {
+    for i in 0..tokens.len() {
+        if matches!(tokens[i].0, Token::Text(_) | Token::Var(_)) {
+            continue;
+        }
+
+        let before_ok = match i.checked_sub(1).map(|j| &tokens[j].0) {
+            None => true,
+            Some(Token::Text(text)) => {
+                let last_line = text.rsplit('\n').next().unwrap_or_default();
+                last_line.trim().is_empty() && (text.contains('\n') || i == 1)
+            }
+            Some(_) => false,
+        };
+        let after_ok = match tokens.get(i + 1).map(|t| &t.0) {
+            None => true,
+            Some(Token::Text(text)) => {
+                let first_line = text.split('\n').next().unwrap_or_default();
+                first_line.trim().is_empty()
+            }
+            Some(_) => false,
+        };
+
+        if !(before_ok && after_ok) {
+            continue;
+        }
+
+        if let Some(Token::Text(text)) = i.checked_sub(1).map(|j| &mut tokens[j].0) {
+            *text = text.trim_end_matches([' ', '\t']);
+        }
+        if let Some(Token::Text(text)) = tokens.get_mut(i + 1).map(|t| &mut t.0) {
+            *text = match text.find('\n') {
+                Some(newline) => &text[newline + 1..],
+                None => "",
+            };
+        }
+    }
+
+    tokens
+}
+
+/// Builds nodes until the end of the input or a block tag closing the current block
+fn parse<'a>(
+    tokens: &mut impl Iterator<Item = Spanned<'a>>,
+) -> Result<(Vec<Node<'a>>, Option<Spanned<'a>>), Box<dyn Error>> {
+    let mut nodes = Vec::new();
+
+    while let Some((token, line)) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text)),
+            Token::Var(name) => nodes.push(Node::Var(name, line)),
+            Token::If(name) => {
+                let (then, end) = parse(tokens)?;
+                let otherwise = match end {
+                    Some((Token::EndIf, _)) => Vec::new(),
+                    Some((Token::Else, else_line)) => match parse(tokens)? {
+                        (otherwise, Some((Token::EndIf, _))) => otherwise,
+                        _ => return Err(unclosed(else_line, "{{else}}")),
+                    },
+                    _ => return Err(unclosed(line, &format!("{{{{#if {name}}}}}"))),
+                };
+
+                nodes.push(Node::If {
+                    name,
+                    then,
+                    otherwise,
+                });
+            }
+            Token::Else | Token::EndIf => return Ok((nodes, Some((token, line)))),
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+fn eval(
+    nodes: &[Node<'_>],
+    vars: &BTreeMap<String, String>,
+    escape: fn(&str) -> String,
+    out: &mut String,
+) -> Result<(), Box<dyn Error>> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(name, line) => {
+                let value = vars
+                    .get(*name)
+                    .ok_or_else(|| format!("line {line}: there is no value for {{{{{name}}}}}"))?;
+                out.push_str(&escape(value));
+            }
+            Node::If {
+                name,
+                then,
+                otherwise,
+            } => {
+                // a missing value is just false, so templates can check optional prompts
+                let set = vars.get(*name).is_some_and(|v| !v.trim().is_empty());
+                eval(if set { then } else { otherwise }, vars, escape, out)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn unclosed(line: usize, tag: &str) -> Box<dyn Error> {
+    format!("line {line}: {tag} is never closed with {{{{/if}}}}").into()
+}
+
+fn describe(token: &Token<'_>) -> &'static str {
+    match token {
+        Token::Else => "{{else}}",
+        _ => "{{/if}}",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_values_and_conditionals() {
+        let source = "# {{title}}\n\n{{#if description}}\n{{description}}\n{{else}}\nA course by {{ author }}\n{{/if}}\n\n---\n";
+
+        let with = render(
+            source,
+            &vars(&[
+                ("title", "IntroToRust"),
+                ("author", "JaneDoe"),
+                ("description", "Learn rust"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(with, "# IntroToRust\n\nLearn rust\n\n---\n");
+
+        let without = render(
+            source,
+            &vars(&[
+                ("title", "IntroToRust"),
+                ("author", "JaneDoe"),
+                ("description", ""),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(without, "# IntroToRust\n\nA course by JaneDoe\n\n---\n");
+
+        // inline blocks keep the text around them
+        let inline = render(
+            "by {{#if author}}{{author}}{{/if}}.",
+            &vars(&[("author", "Jane")]),
+        )
+        .unwrap();
+        assert_eq!(inline, "by Jane.");
+
+        let nested = render(
+            "{{#if a}}{{#if b}}ab{{else}}a{{/if}}{{/if}}",
+            &vars(&[("a", "1")]),
+        )
+        .unwrap();
+        assert_eq!(nested, "a");
+    }
+
+    #[test]
+    fn test_render_html_escapes_values() {
+        let source = r#"<h1 title="{{title}}">{{title}}</h1>"#;
+        let title = vars(&[("title", r#"Rust "&" <script>"#)]);
+
+        assert_eq!(
+            render_html(source, &title).unwrap(),
+            r#"<h1 title="Rust &quot;&amp;&quot; &lt;script&gt;">Rust &quot;&amp;&quot; &lt;script&gt;</h1>"#
+        );
+        // the template itself is left alone
+        assert_eq!(
+            render_html("<b>{{#if title}}set{{/if}}</b>", &title).unwrap(),
+            "<b>set</b>"
+        );
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let empty = BTreeMap::new();
+
+        assert!(render("{{title}}", &empty).is_err());
+        assert!(render("{{#if title}}never closed", &empty).is_err());
+        assert!(render("{{/if}}", &empty).is_err());
+        assert!(render("{{title", &empty).is_err());
+        assert!(render("{{ bad tag }}", &empty).is_err());
+
+        let err = render("line one\n{{missing}}", &empty).unwrap_err();
+        assert!(err.to_string().starts_with("line 2"));
+    }
+}
@@ -0,0 +1,439 @@
+use std::{error::Error, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use strum_macros::{Display, EnumIter, EnumString};
+
+use crate::{config::OsedaConfig, generated::write_if_changed};
+
+/// The generated module `src/main.js` gets its reveal.js options and plugins from
+pub const REVEAL_CONFIG_JS_PATH: &str = "src/reveal.config.js";
+
+/// Slide transitions reveal.js has
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Transition {
+    None,
+    Fade,
+    Slide,
+    Convex,
+    Concave,
+    Zoom,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum TransitionSpeed {
+    Default,
+    Fast,
+    Slow,
+}
+
+/// Plugins a deck can enable, see `oseda plugin add`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Display, EnumIter, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum RevealPlugin {
+    Markdown,
+    Highlight,
+    Notes,
+    // ctrl+shift+f to search the slides
+    Search,
+    // alt+click to zoom in
+    Zoom,
+    // equations with KaTeX, bundled so it works offline
+    Math,
+    // equations with MathJax, loaded from a CDN
+    Mathjax,
+    // mermaid diagrams
+    Diagrams,
+}
+
+// KaTeX's auto-render, run once every chapter is on the page
+const KATEX_PLUGIN: &str = r#"const RevealKatex = {
+  id: "katex",
+  init: (deck) => {
+    deck.on("ready", () => {
+      renderMathInElement(deck.getSlidesElement(), {
+        delimiters: [
+          { left: "$$", right: "$$", display: true },
+          { left: "$", right: "$", display: false },
+          { left: "\\(", right: "\\)", display: false },
+          { left: "\\[", right: "\\]", display: true },
+        ],
+        ignoredTags: ["script", "noscript", "style", "textarea", "pre", "code"],
+      });
+      deck.layout();
+    });
+  },
+};"#;
+
+// turns ```mermaid code blocks into diagrams, along with any <div class="mermaid">
+const MERMAID_PLUGIN: &str = r#"const RevealMermaid = {
+  id: "mermaid",
+  init: (deck) => {
+    mermaid.initialize({ startOnLoad: false });
+    deck.on("ready", async () => {
+      deck.getSlidesElement().querySelectorAll("pre > code.language-mermaid").forEach((code) => {
+        const diagram = document.createElement("div");
+        diagram.className = "mermaid";
+        diagram.textContent = code.textContent;
+        code.parentElement.replaceWith(diagram);
+      });
+      await mermaid.run({ querySelector: ".reveal .mermaid" });
+      deck.layout();
+    });
+  },
+};"#;
+
+impl RevealPlugin {
+    /// What the deck passes to reveal.js for this plugin -> e.g. `RevealMarkdown`
+    fn expression(self) -> &'static str {
+        match self {
+            Self::Markdown => "RevealMarkdown",
+            Self::Highlight => "RevealHighlight",
+            Self::Notes => "RevealNotes",
+            Self::Search => "RevealSearch",
+            Self::Zoom => "RevealZoom",
+            Self::Math => "RevealKatex",
+            Self::Mathjax => "RevealMath.MathJax3",
+            Self::Diagrams => "RevealMermaid",
+        }
+    }
+
+    /// Import statements the plugin needs
+    fn imports(self) -> &'static [&'static str] {
+        match self {
+            Self::Markdown => {
+                &["import RevealMarkdown from \"reveal.js/plugin/markdown/markdown.esm.js\";"]
+            }
+            Self::Highlight => {
+                &["import RevealHighlight from \"reveal.js/plugin/highlight/highlight.esm.js\";"]
+            }
+            Self::Notes => &["import RevealNotes from \"reveal.js/plugin/notes/notes.esm.js\";"],
+            Self::Search => {
+                &["import RevealSearch from \"reveal.js/plugin/search/search.esm.js\";"]
+            }
+            Self::Zoom => &["import RevealZoom from \"reveal.js/plugin/zoom/zoom.esm.js\";"],
+            Self::Math => &[
+                "import renderMathInElement from \"katex/contrib/auto-render\";",
+                "import \"katex/dist/katex.min.css\";",
+            ],
+            Self::Mathjax => &["import RevealMath from \"reveal.js/plugin/math/math.esm.js\";"],
+            Self::Diagrams => &["import mermaid from \"mermaid\";"],
+        }
+    }
+
+    /// Plugins reveal.js doesn't ship are defined in the generated file
+    fn definition(self) -> Option<&'static str> {
+        match self {
+            Self::Math => Some(KATEX_PLUGIN),
+            Self::Diagrams => Some(MERMAID_PLUGIN),
+            _ => None,
+        }
+    }
+
+    /// npm packages the plugin needs on top of reveal.js, pinned under `plugins` in static/package.json
+    pub fn packages(self) -> &'static [&'static str] {
+        match self {
+            Self::Math => &["katex"],
+            Self::Diagrams => &["mermaid"],
+            _ => &[],
+        }
+    }
+}
+
+/// Plugins decks get when the config doesn't list any
+pub const DEFAULT_PLUGINS: [RevealPlugin; 3] = [
+    RevealPlugin::Markdown,
+    RevealPlugin::Highlight,
+    RevealPlugin::Notes,
+];
+
+/// The `reveal` section of an oseda-config.json, the common reveal.js options
+///
+/// Anything left out keeps the template's default
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RevealConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition: Option<Transition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition_speed: Option<TransitionSpeed>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background_transition: Option<Transition>,
+    // arrows in the bottom right corner
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controls: Option<bool>,
+    // progress bar along the bottom
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slide_number: Option<bool>,
+    // vertically center slides
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub center: Option<bool>,
+    // keep the current slide in the url
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<bool>,
+    #[serde(rename = "loop", default, skip_serializing_if = "Option::is_none")]
+    pub loop_slides: Option<bool>,
+    // milliseconds between slides, 0 turns it off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_slide: Option<u32>,
+    // width and height set the aspect ratio slides are scaled with -> e.g. 1280 x 720 for 16:9
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    // space around the slides, as a fraction of the smaller side
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margin: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugins: Option<Vec<RevealPlugin>>,
+}
+
+impl RevealConfig {
+    /// The plugins the deck loads
+    pub fn plugins(&self) -> Vec<RevealPlugin> {
+        self.plugins
+            .clone()
+            .unwrap_or_else(|| DEFAULT_PLUGINS.to_vec())
+    }
+
+    /// Checks the options make sense together
+    ///
+    /// # Arguments
+    /// * `has_markdown` - whether the deck has markdown chapters, those need the markdown plugin
+    ///
+    /// # Returns
+    /// * `Ok(())` if the deck can run with these options
+    /// * `Err(String)` explaining the first problem found
+    pub fn validate(&self, has_markdown: bool) -> Result<(), String> {
+        if self.width.is_some() != self.height.is_some() {
+            return Err("reveal.width and reveal.height must be set together".to_owned());
+        }
+
+        if self.width == Some(0) || self.height == Some(0) {
+            return Err("reveal.width and reveal.height must be more than 0".to_owned());
+        }
+
+        if let Some(margin) = self.margin {
+            if !(0.0..=1.0).contains(&margin) {
+                return Err(format!(
+                    "reveal.margin is {margin}, it must be between 0 and 1"
+                ));
+            }
+        }
+
+        let plugins = self.plugins();
+        for (i, plugin) in plugins.iter().enumerate() {
+            if plugins[..i].contains(plugin) {
+                return Err(format!("reveal.plugins lists {plugin} twice"));
+            }
+        }
+
+        if plugins.contains(&RevealPlugin::Math) && plugins.contains(&RevealPlugin::Mathjax) {
+            return Err(
+                "reveal.plugins has both math and mathjax, they render the same equations"
+                    .to_owned(),
+            );
+        }
+
+        if has_markdown && !plugins.contains(&RevealPlugin::Markdown) {
+            return Err(
+                "reveal.plugins must include markdown, the deck has markdown chapters".to_owned(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The reveal.js options object, in reveal's own camelCase names
+    pub fn options(&self) -> Map<String, Value> {
+        let mut options = Map::new();
+        let mut set = |key: &str, value: Option<Value>| {
+            if let Some(value) = value {
+                options.insert(key.to_owned(), value);
+            }
+        };
+
+        set("transition", self.transition.map(|t| json!(t.to_string())));
+        set(
+            "transitionSpeed",
+            self.transition_speed.map(|s| json!(s.to_string())),
+        );
+        set(
+            "backgroundTransition",
+            self.background_transition.map(|t| json!(t.to_string())),
+        );
+        set("controls", self.controls.map(Value::from));
+        set("progress", self.progress.map(Value::from));
+        set("slideNumber", self.slide_number.map(Value::from));
+        set("center", self.center.map(Value::from));
+        set("hash", self.hash.map(Value::from));
+        set("loop", self.loop_slides.map(Value::from));
+        set("autoSlide", self.auto_slide.map(Value::from));
+        set("width", self.width.map(Value::from));
+        set("height", self.height.map(Value::from));
+        set("margin", self.margin.map(Value::from));
+
+        options
+    }
+}
+
+/// Builds src/reveal.config.js, importing the enabled plugins
+pub fn reveal_config_js(reveal: &RevealConfig) -> String {
+    let plugins = reveal.plugins();
+
+    let mut js = String::from(
+        "// Generated by oseda from \"reveal\" in oseda-config.json, do not edit.\n\
+         // Options left out there keep the defaults from main.js\n\n",
+    );
+
+    for plugin in &plugins {
+        for import in plugin.imports() {
+            js.push_str(import);
+            js.push('\n');
+        }
+    }
+
+    for definition in plugins.iter().filter_map(|plugin| plugin.definition()) {
+        js.push('\n');
+        js.push_str(definition);
+        js.push('\n');
+    }
+
+    let options = serde_json::to_string_pretty(&reveal.options()).unwrap_or_default();
+    let plugin_names: Vec<&str> = plugins.iter().map(|p| p.expression()).collect();
+
+    js.push_str(&format!(
+        "\nexport const options = {options};\n\nexport const plugins = [{}];\n",
+        plugin_names.join(", ")
+    ));
+
+    js
+}
+
+/// The packages enabled plugins need that aren't installed in the project
+///
+/// # Returns
+/// * package names missing from package.json or node_modules/ -> e.g. `katex`
+pub fn missing_packages(project_dir: &Path, reveal: &RevealConfig) -> Vec<String> {
+    let package_json: serde_json::Value = fs::read_to_string(project_dir.join("package.json"))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+
+    reveal
+        .plugins()
+        .iter()
+        .flat_map(|plugin| plugin.packages())
+        .filter(|name| {
+            let listed = ["dependencies", "devDependencies"]
+                .iter()
+                .any(|section| package_json[section].get(**name).is_some());
+            let installed = project_dir
+                .join("node_modules")
+                .join(name)
+                .join("package.json")
+                .exists();
+
+            !(listed && installed)
+        })
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Writes src/reveal.config.js for the `reveal` section of the config, if it changed
+///
+/// # Returns
+/// * `Ok(true)` if src/reveal.config.js was (re)written
+/// * `Ok(false)` if it was already up to date, or the project is an adopted deck
+/// * `Err` if the file can't be written
+pub fn sync_reveal_config(project_dir: &Path, conf: &OsedaConfig) -> Result<bool, Box<dyn Error>> {
+    // adopted decks call Reveal.initialize themselves
+    if conf.adopted {
+        return Ok(false);
+    }
+
+    let js = reveal_config_js(&conf.reveal.clone().unwrap_or_default());
+    write_if_changed(&project_dir.join(REVEAL_CONFIG_JS_PATH), &js)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reveal_config_js() {
+        let reveal: RevealConfig = serde_json::from_str(
+            r#"{
+                "transition": "fade",
+                "slide_number": true,
+                "loop": false,
+                "width": 1280,
+                "height": 720,
+                "plugins": ["markdown", "notes"]
+            }"#,
+        )
+        .unwrap();
+
+        let js = reveal_config_js(&reveal);
+        assert!(js
+            .contains("import RevealMarkdown from \"reveal.js/plugin/markdown/markdown.esm.js\";"));
+        assert!(!js.contains("RevealHighlight"));
+        assert!(js.contains("\"transition\": \"fade\""));
+        assert!(js.contains("\"slideNumber\": true"));
+        assert!(js.contains("\"loop\": false"));
+        assert!(js.contains("export const plugins = [RevealMarkdown, RevealNotes];"));
+
+        // nothing set keeps main.js defaults, with the default plugins
+        let js = reveal_config_js(&RevealConfig::default());
+        assert!(js.contains("export const options = {};"));
+        assert!(js.contains("[RevealMarkdown, RevealHighlight, RevealNotes]"));
+
+        // plugins reveal.js doesn't ship are defined in the file
+        let math = RevealConfig {
+            plugins: Some(vec![RevealPlugin::Markdown, RevealPlugin::Math]),
+            ..Default::default()
+        };
+        let js = reveal_config_js(&math);
+        assert!(js.contains("import renderMathInElement from \"katex/contrib/auto-render\";"));
+        assert!(js.contains("const RevealKatex = {"));
+        assert!(js.contains("export const plugins = [RevealMarkdown, RevealKatex];"));
+
+        assert!(serde_json::from_str::<RevealConfig>(r#"{ "transition": "spin" }"#).is_err());
+    }
+
+    #[test]
+    fn test_validate_reveal_config() {
+        assert!(RevealConfig::default().validate(true).is_ok());
+
+        let half_size = RevealConfig {
+            width: Some(1280),
+            ..Default::default()
+        };
+        assert!(half_size.validate(false).is_err());
+
+        let margin = RevealConfig {
+            margin: Some(1.5),
+            ..Default::default()
+        };
+        assert!(margin.validate(false).is_err());
+
+        let no_markdown = RevealConfig {
+            plugins: Some(vec![RevealPlugin::Highlight]),
+            ..Default::default()
+        };
+        assert!(no_markdown.validate(false).is_ok());
+        assert!(no_markdown.validate(true).is_err());
+
+        let twice = RevealConfig {
+            plugins: Some(vec![RevealPlugin::Notes, RevealPlugin::Notes]),
+            ..Default::default()
+        };
+        assert!(twice.validate(false).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+import Reveal from "reveal.js";
+
+import "reveal.js/dist/reveal.css";
+// the reveal.js theme and highlight style from oseda-config.json, see `oseda theme list`
+import "./theme.js";
+import "../css/theme.css";
+import "../css/custom.css";
+
+// every file in slides/, in chapter order. oseda regenerates this on run and deploy
+import chapters from "./slides.js";
+// the "reveal" section of oseda-config.json, with the plugins it enables
+import { options, plugins } from "./reveal.config.js";
+
+function addChapter(slidesContainer, chapter) {
+  if (chapter.format === "html") {
+    slidesContainer.insertAdjacentHTML("beforeend", chapter.content); // directly inject the HTML slides
+    return;
+  }
+
+  // markdown chapters work here too
+  const section = document.createElement("section");
+  section.setAttribute("data-markdown", "");
+  section.setAttribute("data-separator", "^\\n---\\n");
+
+  const template = document.createElement("textarea");
+  template.setAttribute("data-template", "");
+  template.textContent = chapter.content;
+  section.appendChild(template);
+
+  slidesContainer.appendChild(section);
+}
+
+document.addEventListener("DOMContentLoaded", () => {
+  const slidesContainer = document.querySelector(".slides");
+  chapters.forEach((chapter) => addChapter(slidesContainer, chapter));
+
+  const deck = new Reveal({
+    hash: true,
+    center: false,
+    slideNumber: true,
+    ...options,
+    plugins,
+  });
+
+  deck.initialize();
+});
@@ -0,0 +1,47 @@
+import Reveal from "reveal.js";
+
+import "reveal.js/dist/reveal.css";
+// the reveal.js theme and highlight style from oseda-config.json, see `oseda theme list`
+import "./theme.js";
+import "../css/theme.css";
+import "../css/custom.css";
+
+// every file in slides/, in chapter order. oseda regenerates this on run and deploy
+import chapters from "./slides.js";
+// the "reveal" section of oseda-config.json, with the plugins it enables
+import { options, plugins } from "./reveal.config.js";
+
+function addChapter(slidesContainer, chapter) {
+  // html chapters bring their own <section>s
+  if (chapter.format === "html") {
+    slidesContainer.insertAdjacentHTML("beforeend", chapter.content);
+    return;
+  }
+
+  const section = document.createElement("section");
+  section.setAttribute("data-markdown", "");
+  section.setAttribute("data-separator", "^\\n---\\n");
+  section.setAttribute("data-separator-vertical", "^\\n--\\n");
+  section.setAttribute("data-separator-notes", "^Note:");
+  section.setAttribute("data-charset", "utf-8");
+
+  const template = document.createElement("textarea");
+  template.setAttribute("data-template", "");
+  template.textContent = chapter.content;
+  section.appendChild(template);
+
+  slidesContainer.appendChild(section);
+}
+
+document.addEventListener("DOMContentLoaded", () => {
+  const slidesContainer = document.querySelector(".slides");
+  chapters.forEach((chapter) => addChapter(slidesContainer, chapter));
+
+  const deck = new Reveal({
+    hash: true,
+    ...options,
+    plugins,
+  });
+
+  deck.initialize();
+});
@@ -1,9 +1,523 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt, fs,
+    path::{Component, Path, PathBuf},
+};
+
+use chrono::{Datelike, Utc};
 use serde::{Deserialize, Serialize};
-use strum_macros::{Display, EnumIter, EnumString};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, Display, EnumIter, EnumString)]
-#[strum(ascii_case_insensitive)]
-pub enum Template {
-    HTML,
-    Markdown,
+use crate::{
+    config::{self, OsedaConfig},
+    git::{GitRunner, SystemGit, NETWORK_TIMEOUT},
+    render,
+};
+
+/// Name of the manifest every template directory has at its root
+pub const MANIFEST_FILE: &str = "template.json";
+
+/// A template.json, describing what a template scaffolds
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TemplateManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// files to copy into the project, relative to the template directory
+    pub files: Vec<String>,
+    /// files filled in with the project values, see `render`
+    #[serde(default)]
+    pub render: Vec<String>,
+    /// files the template keeps owning after init, `oseda upgrade` merges new versions into them
+    #[serde(default)]
+    pub upgrade: Vec<String>,
+    /// packages this template needs on top of the ones every project gets, name -> version
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    /// same as `dependencies`, installed as devDependencies
+    #[serde(default)]
+    pub dev_dependencies: BTreeMap<String, String>,
+    /// extra package.json scripts, name -> command
+    #[serde(default)]
+    pub scripts: BTreeMap<String, String>,
+    /// extra values asked for during `oseda init`
+    #[serde(default)]
+    pub prompts: Vec<TemplatePrompt>,
+}
+
+/// A value a template asks the user for during `oseda init`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TemplatePrompt {
+    /// key the answer is saved under -> e.g. `department`
+    pub name: String,
+    /// what to ask -> e.g. `Which department is this course for?`
+    pub message: String,
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// Where a template was found
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateSource {
+    /// embedded in the oseda binary
+    Builtin,
+    /// the user template directory -> e.g. `~/.config/oseda/templates`
+    User(PathBuf),
+    /// a directory passed on the command line
+    Path(PathBuf),
+    /// a git repository, cloned for the duration of `oseda init`
+    Git(String),
+}
+
+impl fmt::Display for TemplateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Builtin => write!(f, "built-in"),
+            Self::User(path) | Self::Path(path) => write!(f, "{}", path.display()),
+            Self::Git(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+/// Files of a template, path relative to the project -> contents
+pub type TemplateFiles = Vec<(String, Vec<u8>)>;
+
+/// A template with all of its files loaded
+#[derive(Debug)]
+pub struct Template {
+    pub manifest: TemplateManifest,
+    pub source: TemplateSource,
+    pub files: TemplateFiles,
+}
+
+// embeds a built-in template directory, the manifest lists the same files
+macro_rules! builtin {
+    ($dir:literal, [$($file:literal),* $(,)?]) => {
+        (
+            include_str!(concat!("static/", $dir, "/template.json")),
+            &[$(($file, include_bytes!(concat!("static/", $dir, "/", $file)) as &[u8])),*],
+        )
+    };
+}
+
+type BuiltinTemplate = (&'static str, &'static [(&'static str, &'static [u8])]);
+
+const BUILTIN_TEMPLATES: [BuiltinTemplate; 2] = [
+    builtin!(
+        "md-templates",
+        [
+            ".gitignore",
+            "index.html",
+            "vite.config.js",
+            "src/main.js",
+            "slides/01-introduction.md",
+            "css/custom.css",
+            "public/ferris.png",
+        ]
+    ),
+    builtin!(
+        "html-templates",
+        [
+            ".gitignore",
+            "index.html",
+            "vite.config.js",
+            "src/main.js",
+            "slides/01-introduction.html",
+            "css/custom.css",
+            "public/ferris.png",
+        ]
+    ),
+];
+
+impl Template {
+    /// Loads a template directory, reading every file its manifest lists
+    ///
+    /// # Returns
+    /// * `Ok(Template)` with all files in memory
+    /// * `Err` if the manifest is missing or invalid, or a listed file can't be read
+    pub fn load_dir(dir: &Path, source: TemplateSource) -> Result<Self, Box<dyn Error>> {
+        let manifest = read_manifest(dir)?;
+
+        let files = manifest
+            .files
+            .iter()
+            .map(|file| {
+                check_relative(file)?;
+                let contents = fs::read(dir.join(file)).map_err(|e| {
+                    format!(
+                        "Template {} lists {file}, but it could not be read: {e}",
+                        manifest.name
+                    )
+                })?;
+                Ok((file.clone(), contents))
+            })
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+        Ok(Template {
+            manifest,
+            source,
+            files,
+        })
+    }
+
+    /// Gets the files to write into a project, rendering the ones the manifest asks for
+    ///
+    /// # Arguments
+    /// * `vars` - values the rendered files can use, see `template_vars`
+    ///
+    /// # Returns
+    /// * `Ok(TemplateFiles)` with the final contents of every file
+    /// * `Err` if a rendered file is not valid UTF-8 or has a template error
+    pub fn render_files(
+        &self,
+        vars: &BTreeMap<String, String>,
+    ) -> Result<TemplateFiles, Box<dyn Error>> {
+        self.files
+            .iter()
+            .map(|(path, contents)| {
+                if !self.manifest.render.contains(path) {
+                    return Ok((path.clone(), contents.clone()));
+                }
+
+                let source = std::str::from_utf8(contents).map_err(|_| {
+                    format!("Template file {path} is not text, it can't be rendered")
+                })?;
+                // values end up in markup as text, not as more markup
+                let is_html = Path::new(path)
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("html"));
+                let rendered = if is_html {
+                    render::render_html(source, vars)
+                } else {
+                    render::render(source, vars)
+                };
+                let rendered =
+                    rendered.map_err(|e| format!("Could not render template file {path}, {e}"))?;
+
+                Ok((path.clone(), rendered.into_bytes()))
+            })
+            .collect()
+    }
+
+    /// Renders and writes every template file into a project directory
+    pub fn write_to(
+        &self,
+        project_dir: &Path,
+        vars: &BTreeMap<String, String>,
+    ) -> Result<(), Box<dyn Error>> {
+        for (path, contents) in self.render_files(vars)? {
+            let dest = project_dir.join(path);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(dest, contents)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Gets the values templates can use from a project config
+///
+/// Answers to the template's prompts are included, the project values win
+/// if a prompt has the same name -> e.g. `title`, `author`, `color`, `description`, `tags`, `year`
+pub fn template_vars(conf: &OsedaConfig) -> BTreeMap<String, String> {
+    let mut vars = conf
+        .template
+        .as_ref()
+        .map(|info| info.values.clone())
+        .unwrap_or_default();
+
+    let tags = conf
+        .tags
+        .iter()
+        .map(|tag| tag.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    vars.extend([
+        ("title".to_owned(), conf.title.clone()),
+        ("author".to_owned(), conf.author.clone()),
+        ("color".to_owned(), conf.color.clone()),
+        ("description".to_owned(), conf.description.clone()),
+        ("tags".to_owned(), tags),
+        ("year".to_owned(), Utc::now().year().to_string()),
+    ]);
+
+    vars
+}
+
+/// Gets the templates embedded in the binary
+pub fn builtin_templates() -> Vec<Template> {
+    BUILTIN_TEMPLATES
+        .iter()
+        .map(|(manifest, files)| Template {
+            manifest: serde_json::from_str(manifest).expect("built-in template.json is valid"),
+            source: TemplateSource::Builtin,
+            files: files
+                .iter()
+                .map(|(path, contents)| (path.to_string(), contents.to_vec()))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Gets the directory users can drop their own templates in
+///
+/// Each subdirectory with a template.json is a template -> e.g. `~/.config/oseda/templates/department`
+pub fn user_template_dir() -> Option<PathBuf> {
+    config::global_config_dir().map(|dir| dir.join("templates"))
+}
+
+/// Lists every template `oseda init --template <name>` can find by name
+///
+/// User templates come first, and shadow built-in templates with the same name
+pub fn list() -> Result<Vec<Template>, Box<dyn Error>> {
+    let mut templates = match user_template_dir() {
+        Some(dir) => user_templates(&dir),
+        None => Vec::new(),
+    };
+
+    for builtin in builtin_templates() {
+        if !templates
+            .iter()
+            .any(|t| t.manifest.name.eq_ignore_ascii_case(&builtin.manifest.name))
+        {
+            templates.push(builtin);
+        }
+    }
+
+    Ok(templates)
+}
+
+/// Loads the templates in the user template directory
+///
+/// A broken template is skipped with a warning, it shouldn't take the built-in ones down with it
+fn user_templates(dir: &Path) -> Vec<Template> {
+    if !dir.is_dir() {
+        return Vec::new();
+    }
+
+    let mut dirs = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.join(MANIFEST_FILE).is_file())
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            eprintln!(
+                "Warning: could not read templates in {}: {e}",
+                dir.display()
+            );
+            return Vec::new();
+        }
+    };
+    dirs.sort();
+
+    dirs.into_iter()
+        .filter_map(
+            |path| match Template::load_dir(&path, TemplateSource::User(path.clone())) {
+                Ok(template) => Some(template),
+                Err(e) => {
+                    eprintln!("Warning: skipping template {}: {e}", path.display());
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Finds the template `oseda init --template` refers to
+///
+/// # Arguments
+/// * `spec` - a template name, a path to a template directory, or a git URL
+///
+/// # Returns
+/// * `Ok(Template)` with all of its files loaded
+/// * `Err` if nothing matches, or the template could not be loaded
+pub fn resolve(spec: &str) -> Result<Template, Box<dyn Error>> {
+    if is_git_url(spec) {
+        return clone_template(spec);
+    }
+
+    let path = Path::new(spec);
+    if path.join(MANIFEST_FILE).is_file() {
+        return Template::load_dir(path, TemplateSource::Path(path.to_owned()));
+    }
+
+    list()?
+        .into_iter()
+        .find(|t| t.manifest.name.eq_ignore_ascii_case(spec))
+        .ok_or_else(|| {
+            format!("Could not find template {spec}, run `oseda template list` to see what is available")
+                .into()
+        })
+}
+
+fn is_git_url(spec: &str) -> bool {
+    ["https://", "http://", "ssh://", "git@", "file://"]
+        .iter()
+        .any(|prefix| spec.starts_with(prefix))
+        || spec.ends_with(".git")
+}
+
+/// Clones a template repository and loads it, the clone is thrown away afterwards
+fn clone_template(url: &str) -> Result<Template, Box<dyn Error>> {
+    let tmp_dir = tempfile::tempdir()?;
+
+    println!("Fetching template from {url}...");
+    SystemGit::new().with_network_timeout(NETWORK_TIMEOUT).run(
+        tmp_dir.path(),
+        &["clone", "--quiet", "--depth", "1", url, "."],
+    )?;
+
+    Template::load_dir(tmp_dir.path(), TemplateSource::Git(url.to_owned()))
+}
+
+fn read_manifest(dir: &Path) -> Result<TemplateManifest, Box<dyn Error>> {
+    let path = dir.join(MANIFEST_FILE);
+    let manifest = fs::read_to_string(&path).map_err(|_| {
+        format!(
+            "{} is not a template, it has no {MANIFEST_FILE}",
+            dir.display()
+        )
+    })?;
+
+    serde_json::from_str(&manifest)
+        .map_err(|e| format!("Could not parse {}: {e}", path.display()).into())
+}
+
+/// Template files must stay inside the project they are written to
+fn check_relative(file: &str) -> Result<(), Box<dyn Error>> {
+    let escapes = Path::new(file)
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+
+    if escapes {
+        return Err(
+            format!("Template file {file} must be a relative path inside the template").into(),
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_builtin_templates_match_manifests() {
+        let templates = builtin_templates();
+
+        let names: Vec<&str> = templates.iter().map(|t| t.manifest.name.as_str()).collect();
+        assert_eq!(names, vec!["markdown", "html"]);
+
+        for template in templates {
+            let embedded: Vec<&str> = template.files.iter().map(|(p, _)| p.as_str()).collect();
+            assert_eq!(embedded, template.manifest.files);
+            for owned in &template.manifest.upgrade {
+                assert!(template.manifest.files.contains(owned));
+            }
+        }
+    }
+
+    #[test]
+    fn test_builtin_templates_render() {
+        let conf: OsedaConfig = serde_json::from_str(
+            r##"{
+                "title": "IntroToRust",
+                "author": "JaneDoe",
+                "tags": ["ComputerScience"],
+                "last_updated": "2024-07-10T12:34:56Z",
+                "color": "#000000",
+                "description": ""
+            }"##,
+        )
+        .unwrap();
+        let vars = template_vars(&conf);
+
+        for template in builtin_templates() {
+            let files = template.render_files(&vars).unwrap();
+            let file = |name: &str| {
+                let (_, contents) = files.iter().find(|(path, _)| path == name).unwrap();
+                String::from_utf8(contents.clone()).unwrap()
+            };
+
+            assert!(file("index.html").contains("<title>IntroToRust</title>"));
+            let slides = template
+                .manifest
+                .files
+                .iter()
+                .find(|f| f.starts_with("slides/"))
+                .unwrap();
+            assert!(file(slides).contains("by JaneDoe"));
+            assert!(!file(slides).contains("{{"));
+        }
+    }
+
+    #[test]
+    fn test_load_dir_template() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("slides")).unwrap();
+        fs::write(dir.path().join("slides/slides.md"), "# Department").unwrap();
+        fs::write(
+            dir.path().join(MANIFEST_FILE),
+            r#"{
+                "name": "department",
+                "version": "0.2.0",
+                "files": ["slides/slides.md"],
+                "dependencies": { "reveal.js": "5.2.1" },
+                "prompts": [{ "name": "department", "message": "Department?" }]
+            }"#,
+        )
+        .unwrap();
+
+        let template =
+            Template::load_dir(dir.path(), TemplateSource::Path(dir.path().into())).unwrap();
+        assert_eq!(template.manifest.name, "department");
+        assert_eq!(template.manifest.prompts[0].name, "department");
+        assert_eq!(template.files[0].1, b"# Department");
+
+        let project = tempfile::tempdir().unwrap();
+        template.write_to(project.path(), &BTreeMap::new()).unwrap();
+        assert!(project.path().join("slides/slides.md").exists());
+
+        let found = resolve(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(found.manifest.version, "0.2.0");
+    }
+
+    #[test]
+    fn test_user_templates_skip_broken_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("department");
+        let broken = dir.path().join("broken");
+        fs::create_dir_all(&good).unwrap();
+        fs::create_dir_all(&broken).unwrap();
+        fs::write(
+            good.join(MANIFEST_FILE),
+            r#"{ "name": "department", "version": "0.1.0", "files": [] }"#,
+        )
+        .unwrap();
+        fs::write(broken.join(MANIFEST_FILE), "{ not json").unwrap();
+
+        let templates = user_templates(dir.path());
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].manifest.name, "department");
+    }
+
+    #[test]
+    fn test_template_files_stay_in_project() {
+        assert!(check_relative("css/custom.css").is_ok());
+        assert!(check_relative("../escape.js").is_err());
+        assert!(check_relative("/etc/passwd").is_err());
+
+        assert!(is_git_url(
+            "https://github.com/oseda-dev/department-template"
+        ));
+        assert!(is_git_url(
+            "git@github.com:oseda-dev/department-template.git"
+        ));
+        assert!(!is_git_url("markdown"));
+    }
 }
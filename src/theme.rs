@@ -0,0 +1,746 @@
+use std::{error::Error, path::Path};
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::{config::OsedaConfig, generated::write_if_changed};
+
+/// Where the generated theme lives inside a project
+pub const THEME_CSS_PATH: &str = "css/theme.css";
+
+/// The generated module importing the reveal.js theme and highlight style, loaded by `src/main.js`
+pub const THEME_JS_PATH: &str = "src/theme.js";
+
+const WHITE: Rgb = Rgb {
+    r: 0xFF,
+    g: 0xFF,
+    b: 0xFF,
+};
+const BLACK: Rgb = Rgb { r: 0, g: 0, b: 0 };
+
+// WCAG minimum contrast for large text (headings) and body text (links)
+const HEADING_CONTRAST: f64 = 3.0;
+const TEXT_CONTRAST: f64 = 4.5;
+
+/// An sRGB color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+// shorthand for the theme tables -> e.g. `rgb(0x191919)`
+const fn rgb(hex: u32) -> Rgb {
+    Rgb {
+        r: (hex >> 16) as u8,
+        g: (hex >> 8) as u8,
+        b: hex as u8,
+    }
+}
+
+impl Rgb {
+    /// Parses a hex color -> e.g. `#FF0000`, `ff0000` or `#f00`
+    pub fn from_hex(hex: &str) -> Option<Rgb> {
+        let hex = hex.trim().trim_start_matches('#');
+        let hex = match hex.len() {
+            3 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+            6 => hex.to_owned(),
+            _ => return None,
+        };
+
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+        Some(Rgb {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        })
+    }
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+
+    /// Relative luminance as defined by WCAG, 0 for black and 1 for white
+    pub fn luminance(self) -> f64 {
+        let linear = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// WCAG contrast ratio between two colors, from 1 (same) to 21 (black on white)
+    pub fn contrast(self, other: Rgb) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        let (light, dark) = if a > b { (a, b) } else { (b, a) };
+
+        (light + 0.05) / (dark + 0.05)
+    }
+
+    /// Mixes in `amount` (0 to 1) of another color
+    fn mix(self, other: Rgb, amount: f64) -> Rgb {
+        let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * amount).round() as u8;
+
+        Rgb {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+        }
+    }
+
+    /// Lightens or darkens the color just enough to reach a contrast against `background`
+    fn readable_on(self, background: Rgb, min_contrast: f64) -> Rgb {
+        let toward = background.opposite();
+
+        (0..=20)
+            .map(|step| self.mix(toward, step as f64 / 20.0))
+            .find(|color| color.contrast(background) >= min_contrast)
+            .unwrap_or(toward)
+    }
+
+    /// White on dark colors, black on light ones
+    fn opposite(self) -> Rgb {
+        if self.luminance() < 0.5 {
+            WHITE
+        } else {
+            BLACK
+        }
+    }
+}
+
+/// Whether a theme has light text on a dark background, or the other way around
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Display, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum Variant {
+    Dark,
+    Light,
+}
+
+/// Where a theme comes from
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+pub enum ThemeSource {
+    #[strum(serialize = "reveal.js")]
+    Reveal,
+    #[strum(serialize = "oseda")]
+    Oseda,
+}
+
+/// One variant of a theme
+#[derive(Debug)]
+pub struct Look {
+    /// the reveal.js theme it builds on -> e.g. `black` for `reveal.js/dist/theme/black.css`
+    pub reveal_theme: &'static str,
+    pub background: Rgb,
+    pub text: Rgb,
+    /// highlight style used unless the config picks one
+    pub highlight: &'static str,
+    /// styles on top of the reveal.js theme, appended to css/theme.css
+    pub css: &'static str,
+}
+
+/// A named theme `oseda theme apply` can use
+#[derive(Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub source: ThemeSource,
+    pub dark: Option<Look>,
+    pub light: Option<Look>,
+}
+
+impl Theme {
+    pub fn look(&self, variant: Variant) -> Option<&Look> {
+        match variant {
+            Variant::Dark => self.dark.as_ref(),
+            Variant::Light => self.light.as_ref(),
+        }
+    }
+
+    /// The variants the theme has, dark first
+    pub fn variants(&self) -> Vec<Variant> {
+        [Variant::Dark, Variant::Light]
+            .into_iter()
+            .filter(|variant| self.look(*variant).is_some())
+            .collect()
+    }
+}
+
+// a reveal.js theme as is, they only come in one variant
+const fn reveal_theme(
+    name: &'static str,
+    description: &'static str,
+    variant: Variant,
+    background: u32,
+    text: u32,
+) -> Theme {
+    let look = Look {
+        reveal_theme: name,
+        background: rgb(background),
+        text: rgb(text),
+        highlight: match variant {
+            Variant::Dark => "monokai",
+            Variant::Light => "github",
+        },
+        css: "",
+    };
+
+    match variant {
+        Variant::Dark => Theme {
+            name,
+            description,
+            source: ThemeSource::Reveal,
+            dark: Some(look),
+            light: None,
+        },
+        Variant::Light => Theme {
+            name,
+            description,
+            source: ThemeSource::Reveal,
+            dark: None,
+            light: Some(look),
+        },
+    }
+}
+
+/// Every theme, the built-in reveal.js ones and the ones bundled with oseda
+pub const THEMES: &[Theme] = &[
+    reveal_theme(
+        "black",
+        "reveal.js default, white on black",
+        Variant::Dark,
+        0x191919,
+        0xFFFFFF,
+    ),
+    reveal_theme(
+        "white",
+        "black on white",
+        Variant::Light,
+        0xFFFFFF,
+        0x222222,
+    ),
+    reveal_theme(
+        "league",
+        "gray gradient background",
+        Variant::Dark,
+        0x1C1E20,
+        0xEEEEEE,
+    ),
+    reveal_theme(
+        "beige",
+        "beige background",
+        Variant::Light,
+        0xF7F3DE,
+        0x333333,
+    ),
+    reveal_theme(
+        "sky",
+        "light blue background",
+        Variant::Light,
+        0xF7FBFC,
+        0x333333,
+    ),
+    reveal_theme(
+        "night",
+        "black background, thick white text",
+        Variant::Dark,
+        0x111111,
+        0xEEEEEE,
+    ),
+    reveal_theme(
+        "serif",
+        "cappuccino background, serif fonts",
+        Variant::Light,
+        0xF0F1EB,
+        0x000000,
+    ),
+    reveal_theme(
+        "simple",
+        "white background, black text",
+        Variant::Light,
+        0xFFFFFF,
+        0x000000,
+    ),
+    reveal_theme(
+        "solarized",
+        "cream colored background",
+        Variant::Light,
+        0xFDF6E3,
+        0x657B83,
+    ),
+    reveal_theme(
+        "blood",
+        "dark background, thick white text, red links",
+        Variant::Dark,
+        0x222222,
+        0xEEEEEE,
+    ),
+    reveal_theme(
+        "moon",
+        "dark blue background",
+        Variant::Dark,
+        0x002B36,
+        0x93A1A1,
+    ),
+    reveal_theme(
+        "dracula",
+        "dracula colors",
+        Variant::Dark,
+        0x282A36,
+        0xF8F8F2,
+    ),
+    reveal_theme(
+        "black-contrast",
+        "black with higher contrast",
+        Variant::Dark,
+        0x000000,
+        0xFFFFFF,
+    ),
+    reveal_theme(
+        "white-contrast",
+        "white with higher contrast",
+        Variant::Light,
+        0xFFFFFF,
+        0x000000,
+    ),
+    Theme {
+        name: "oseda",
+        description: "sans-serif, accent underlined headings",
+        source: ThemeSource::Oseda,
+        dark: Some(Look {
+            reveal_theme: "black",
+            background: rgb(0x15171C),
+            text: rgb(0xE8E8E8),
+            highlight: "atom-one-dark",
+            css: include_str!("static/themes/oseda-dark.css"),
+        }),
+        light: Some(Look {
+            reveal_theme: "white",
+            background: rgb(0xFAFAF7),
+            text: rgb(0x1F2328),
+            highlight: "atom-one-light",
+            css: include_str!("static/themes/oseda-light.css"),
+        }),
+    },
+    Theme {
+        name: "lecture",
+        description: "large serif text for lecture halls",
+        source: ThemeSource::Oseda,
+        dark: Some(Look {
+            reveal_theme: "black",
+            background: rgb(0x1B1D1A),
+            text: rgb(0xF2F0E6),
+            highlight: "a11y-dark",
+            css: include_str!("static/themes/lecture-dark.css"),
+        }),
+        light: Some(Look {
+            reveal_theme: "white",
+            background: rgb(0xFFFDF5),
+            text: rgb(0x111111),
+            highlight: "a11y-light",
+            css: include_str!("static/themes/lecture-light.css"),
+        }),
+    },
+];
+
+/// A highlight.js style for code blocks
+#[derive(Debug)]
+pub struct HighlightStyle {
+    pub name: &'static str,
+    /// stylesheet import -> e.g. `highlight.js/styles/github.css`
+    pub path: &'static str,
+    pub variant: Variant,
+}
+
+const fn highlight(name: &'static str, path: &'static str, variant: Variant) -> HighlightStyle {
+    HighlightStyle {
+        name,
+        path,
+        variant,
+    }
+}
+
+/// Highlight styles shipped with reveal.js and highlight.js
+pub const HIGHLIGHT_STYLES: &[HighlightStyle] = &[
+    highlight(
+        "monokai",
+        "reveal.js/plugin/highlight/monokai.css",
+        Variant::Dark,
+    ),
+    highlight(
+        "zenburn",
+        "reveal.js/plugin/highlight/zenburn.css",
+        Variant::Dark,
+    ),
+    highlight(
+        "a11y-dark",
+        "highlight.js/styles/a11y-dark.css",
+        Variant::Dark,
+    ),
+    highlight(
+        "a11y-light",
+        "highlight.js/styles/a11y-light.css",
+        Variant::Light,
+    ),
+    highlight(
+        "atom-one-dark",
+        "highlight.js/styles/atom-one-dark.css",
+        Variant::Dark,
+    ),
+    highlight(
+        "atom-one-light",
+        "highlight.js/styles/atom-one-light.css",
+        Variant::Light,
+    ),
+    highlight("github", "highlight.js/styles/github.css", Variant::Light),
+    highlight(
+        "github-dark",
+        "highlight.js/styles/github-dark.css",
+        Variant::Dark,
+    ),
+    highlight("nord", "highlight.js/styles/nord.css", Variant::Dark),
+    highlight(
+        "stackoverflow-dark",
+        "highlight.js/styles/stackoverflow-dark.css",
+        Variant::Dark,
+    ),
+    highlight(
+        "stackoverflow-light",
+        "highlight.js/styles/stackoverflow-light.css",
+        Variant::Light,
+    ),
+    highlight(
+        "tokyo-night-dark",
+        "highlight.js/styles/tokyo-night-dark.css",
+        Variant::Dark,
+    ),
+    highlight(
+        "tokyo-night-light",
+        "highlight.js/styles/tokyo-night-light.css",
+        Variant::Light,
+    ),
+    highlight("vs", "highlight.js/styles/vs.css", Variant::Light),
+    highlight("vs2015", "highlight.js/styles/vs2015.css", Variant::Dark),
+];
+
+/// The `theme` section of an oseda-config.json
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ThemeConfig {
+    /// one of `THEMES` -> e.g. `oseda`
+    pub name: String,
+    // dark when the theme has it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<Variant>,
+    // the look's own highlight style when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<String>,
+}
+
+/// The theme a project ends up with
+#[derive(Debug)]
+pub struct ResolvedTheme {
+    pub theme: &'static Theme,
+    pub variant: Variant,
+    pub look: &'static Look,
+    pub highlight: &'static HighlightStyle,
+}
+
+pub fn find_theme(name: &str) -> Option<&'static Theme> {
+    THEMES.iter().find(|theme| theme.name == name)
+}
+
+pub fn find_highlight(name: &str) -> Option<&'static HighlightStyle> {
+    HIGHLIGHT_STYLES.iter().find(|style| style.name == name)
+}
+
+/// Looks up the theme in the config
+///
+/// # Arguments
+/// * `conf` - the `theme` section of the config, black with monokai when unset
+///
+/// # Returns
+/// * `Ok(ResolvedTheme)` with the look and highlight style to use
+/// * `Err(String)` if the theme, variant or highlight style doesn't exist
+pub fn resolve(conf: Option<&ThemeConfig>) -> Result<ResolvedTheme, String> {
+    let name = conf.map_or("black", |conf| conf.name.as_str());
+    let theme = find_theme(name)
+        .ok_or_else(|| format!("Theme {name} does not exist, see `oseda theme list`"))?;
+
+    let variant = match conf.and_then(|conf| conf.variant) {
+        Some(variant) => variant,
+        None => theme.variants()[0],
+    };
+    let look = theme
+        .look(variant)
+        .ok_or_else(|| format!("Theme {name} has no {variant} variant, see `oseda theme list`"))?;
+
+    let highlight_name = conf
+        .and_then(|conf| conf.highlight.as_deref())
+        .unwrap_or(look.highlight);
+    let highlight = find_highlight(highlight_name).ok_or_else(|| {
+        format!("Highlight style {highlight_name} does not exist, see `oseda theme list`")
+    })?;
+
+    Ok(ResolvedTheme {
+        theme,
+        variant,
+        look,
+        highlight,
+    })
+}
+
+/// The colors a course uses, all derived from the color in its config
+#[derive(Debug, PartialEq)]
+pub struct Palette {
+    /// the configured color, as is
+    pub accent: Rgb,
+    /// black or white, whichever reads better on top of the accent
+    pub accent_text: Rgb,
+    pub heading: Rgb,
+    pub link: Rgb,
+    pub link_hover: Rgb,
+    pub progress: Rgb,
+}
+
+impl Palette {
+    /// Derives a palette that stays readable on the slide background
+    ///
+    /// # Arguments
+    /// * `accent` - the color from the config
+    /// * `background` - background of the theme's slides
+    pub fn from_accent(accent: Rgb, background: Rgb) -> Palette {
+        let accent_text = if accent.contrast(WHITE) >= accent.contrast(BLACK) {
+            WHITE
+        } else {
+            BLACK
+        };
+
+        let link = accent.readable_on(background, TEXT_CONTRAST);
+
+        Palette {
+            accent,
+            accent_text,
+            heading: accent.readable_on(background, HEADING_CONTRAST),
+            link,
+            link_hover: link.mix(background.opposite(), 0.3),
+            // the progress bar is thin, it only needs to be visible
+            progress: accent.readable_on(background, HEADING_CONTRAST),
+        }
+    }
+}
+
+/// Builds the css/theme.css for a course color
+///
+/// # Arguments
+/// * `color` - hex color from the oseda-config.json -> e.g. `#FF0000`
+/// * `look` - the theme the colors have to stay readable on
+///
+/// # Returns
+/// * `Ok(String)` with the stylesheet
+/// * `Err` if the color is not a hex color
+pub fn theme_css(color: &str, look: &Look) -> Result<String, Box<dyn Error>> {
+    let accent = Rgb::from_hex(color)
+        .ok_or_else(|| format!("Color {color} is not a hex color -> e.g. #FF0000"))?;
+    let palette = Palette::from_accent(accent, look.background);
+
+    let mut css = format!(
+        r#"/* Generated by oseda from the color in oseda-config.json, do not edit.
+   This file is rewritten on every run and deploy, put your own styles in custom.css */
+
+:root {{
+  --oseda-accent: {accent};
+  --oseda-accent-text: {accent_text};
+  --oseda-heading: {heading};
+  --oseda-link: {link};
+  --oseda-link-hover: {link_hover};
+  --oseda-progress: {progress};
+
+  /* reveal.js theme variables */
+  --r-heading-color: var(--oseda-heading);
+  --r-link-color: var(--oseda-link);
+  --r-link-color-hover: var(--oseda-link-hover);
+  --r-selection-background-color: var(--oseda-accent);
+  --r-selection-color: var(--oseda-accent-text);
+}}
+
+.reveal .progress {{
+  color: var(--oseda-progress);
+}}
+
+.reveal .controls {{
+  color: var(--oseda-link);
+}}
+"#,
+        accent = palette.accent.to_hex(),
+        accent_text = palette.accent_text.to_hex(),
+        heading = palette.heading.to_hex(),
+        link = palette.link.to_hex(),
+        link_hover = palette.link_hover.to_hex(),
+        progress = palette.progress.to_hex(),
+    );
+
+    if !look.css.is_empty() {
+        css.push('\n');
+        css.push_str(look.css);
+    }
+
+    Ok(css)
+}
+
+/// Builds the src/theme.js importing the reveal.js theme and highlight style
+pub fn theme_js(theme: &ResolvedTheme) -> String {
+    format!(
+        "// Generated by oseda from \"theme\" in oseda-config.json, do not edit.\n\
+         // Change it with `oseda theme apply`, see `oseda theme list`\n\n\
+         import \"reveal.js/dist/theme/{}.css\";\n\
+         import \"{}\";\n",
+        theme.look.reveal_theme, theme.highlight.path
+    )
+}
+
+/// Writes css/theme.css and src/theme.js for the theme and color in the config, if they changed
+///
+/// # Arguments
+/// * `project_dir` - the project to write the theme into
+/// * `conf` - the project's config
+///
+/// # Returns
+/// * `Ok(true)` if the theme was (re)written
+/// * `Ok(false)` if it was already up to date
+/// * `Err` if the color or theme is invalid or a file can't be written
+pub fn sync_theme(project_dir: &Path, conf: &OsedaConfig) -> Result<bool, Box<dyn Error>> {
+    let theme = resolve(conf.theme.as_ref())?;
+
+    let css = theme_css(&conf.color, theme.look)?;
+    let css_changed = write_if_changed(&project_dir.join(THEME_CSS_PATH), &css)?;
+    let js_changed = write_if_changed(&project_dir.join(THEME_JS_PATH), &theme_js(&theme))?;
+
+    Ok(css_changed || js_changed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(Rgb::from_hex("#FF0000"), Some(Rgb { r: 255, g: 0, b: 0 }));
+        assert_eq!(Rgb::from_hex("#f00"), Rgb::from_hex("ff0000"));
+        assert_eq!(Rgb::from_hex("#GG0000"), None);
+        assert_eq!(Rgb::from_hex("red"), None);
+        assert_eq!(Rgb::from_hex("#ffa500").unwrap().to_hex(), "#FFA500");
+    }
+
+    #[test]
+    fn test_palette_stays_readable() {
+        let backgrounds: Vec<Rgb> = THEMES
+            .iter()
+            .flat_map(|theme| [&theme.dark, &theme.light])
+            .flatten()
+            .map(|look| look.background)
+            .collect();
+
+        for hex in [
+            "#000000", "#000080", "#800000", "#FFFF00", "#FFFFFF", "#FF0000",
+        ] {
+            for background in &backgrounds {
+                let palette = Palette::from_accent(Rgb::from_hex(hex).unwrap(), *background);
+
+                assert!(palette.heading.contrast(*background) >= HEADING_CONTRAST);
+                assert!(palette.link.contrast(*background) >= TEXT_CONTRAST);
+                assert!(palette.accent.contrast(palette.accent_text) >= 3.0);
+            }
+        }
+
+        // readable colors are used as is
+        let black = rgb(0x191919);
+        let yellow = Palette::from_accent(Rgb::from_hex("#FFFF00").unwrap(), black);
+        assert_eq!(yellow.heading.to_hex(), "#FFFF00");
+        assert_eq!(yellow.accent_text, BLACK);
+
+        let navy = Palette::from_accent(Rgb::from_hex("#000080").unwrap(), black);
+        assert_ne!(navy.heading.to_hex(), "#000080");
+        assert_eq!(navy.accent_text, WHITE);
+
+        // but not on a light theme
+        let white = Palette::from_accent(Rgb::from_hex("#FFFF00").unwrap(), WHITE);
+        assert_ne!(white.heading.to_hex(), "#FFFF00");
+    }
+
+    #[test]
+    fn test_sync_theme_only_writes_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut conf: OsedaConfig = serde_json::from_str(
+            r##"{
+                "title": "IntroToRust",
+                "author": "JaneDoe",
+                "tags": ["ComputerScience"],
+                "last_updated": "2024-07-10T12:34:56Z",
+                "color": "#FF0000",
+                "description": "Learn rust"
+            }"##,
+        )
+        .unwrap();
+
+        assert!(sync_theme(dir.path(), &conf).unwrap());
+        assert!(!sync_theme(dir.path(), &conf).unwrap());
+
+        conf.color = "#008080".to_owned();
+        assert!(sync_theme(dir.path(), &conf).unwrap());
+
+        let css = fs::read_to_string(dir.path().join(THEME_CSS_PATH)).unwrap();
+        assert!(css.contains("--oseda-accent: #008080;"));
+
+        conf.color = "teal".to_owned();
+        assert!(sync_theme(dir.path(), &conf).is_err());
+    }
+
+    #[test]
+    fn test_resolve_theme() {
+        let default = resolve(None).unwrap();
+        assert_eq!(default.theme.name, "black");
+        assert_eq!(
+            default.highlight.path,
+            "reveal.js/plugin/highlight/monokai.css"
+        );
+        assert!(theme_js(&default).contains("import \"reveal.js/dist/theme/black.css\";"));
+
+        let mut conf = ThemeConfig {
+            name: "oseda".to_owned(),
+            variant: Some(Variant::Light),
+            highlight: None,
+        };
+        let light = resolve(Some(&conf)).unwrap();
+        assert_eq!(light.look.reveal_theme, "white");
+        assert_eq!(light.highlight.name, "atom-one-light");
+
+        conf.highlight = Some("nord".to_owned());
+        assert_eq!(resolve(Some(&conf)).unwrap().highlight.name, "nord");
+
+        conf.highlight = Some("rainbow".to_owned());
+        assert!(resolve(Some(&conf)).is_err());
+
+        // reveal.js themes only come in one variant
+        let moon = ThemeConfig {
+            name: "moon".to_owned(),
+            variant: Some(Variant::Light),
+            highlight: None,
+        };
+        assert!(resolve(Some(&moon)).is_err());
+
+        // every default highlight style exists
+        for theme in THEMES {
+            for variant in theme.variants() {
+                assert!(find_highlight(theme.look(variant).unwrap().highlight).is_some());
+            }
+        }
+    }
+}